@@ -2,13 +2,14 @@ use bevy::{
     asset::{AssetLoader, AsyncReadExt},
     prelude::*,
 };
-use jc2_file_formats::archive::{ArchiveTable, ArchiveTableEntry, StreamArchive};
+use jc2_file_formats::archive::{
+    ArchiveKind, ArchiveTable, ArchiveTableEntry, StreamArchiveBuffer, StreamArchiveSlice,
+};
 #[cfg(feature = "tree")]
-use jc2_hashing::HashList;
+use jc2_hashing::{HashCollisions, HashList};
 use jc2_hashing::HashString;
 use std::{
     collections::HashMap,
-    ffi::OsStr,
     path::{Path, PathBuf},
 };
 use thiserror::Error;
@@ -29,7 +30,7 @@ pub(crate) enum ArchiveError {
 #[derive(Debug, Clone)]
 pub(crate) enum ArchiveEntry {
     Streamed(ArchiveTableEntry),
-    Preloaded(Vec<u8>),
+    Preloaded(StreamArchiveSlice),
 }
 
 #[cfg(feature = "tree")]
@@ -65,35 +66,35 @@ impl AssetLoader for ArchiveLoader {
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let mut cursor = binrw::io::Cursor::new(&bytes);
-
         let source_path = load_context.path().to_path_buf();
         let hash = HashString::from_str(&source_path.to_string_lossy());
 
         match archive_type(load_context.path()) {
             ArchiveType::Stream => {
-                let archive = StreamArchive::read(&mut cursor)?;
+                // Entries share the archive's buffer, rather than each holding a copy
+                let archive = StreamArchiveBuffer::new(bytes)?;
                 Ok(Archive {
                     hash,
                     source_path,
                     target_path: None,
                     #[cfg(feature = "tree")]
                     paths: ArchivePaths::HashList({
-                        let mut paths = HashList::with_capacity(archive.entries.len());
-                        for path in archive.entries.keys() {
-                            paths.insert_path(path);
+                        let entries = &archive.index().entries;
+                        let mut paths = HashList::with_capacity(entries.len())
+                            .with_collisions(HashCollisions::Keep);
+                        for entry in entries {
+                            paths.insert_path(&entry.name);
                         }
                         paths
                     }),
                     entries: archive
-                        .entries
-                        .into_iter()
-                        .map(|(k, v)| (HashString::from_str(&k), ArchiveEntry::Preloaded(v)))
+                        .iter()
+                        .map(|(k, v)| (HashString::from_str(k), ArchiveEntry::Preloaded(v)))
                         .collect(),
                 })
             }
             ArchiveType::File => {
-                let archive = ArchiveTable::read(&mut cursor)?;
+                let archive = ArchiveTable::read(&mut binrw::io::Cursor::new(&bytes))?;
                 Ok(Archive {
                     hash,
                     source_path: source_path.clone(),
@@ -121,9 +122,9 @@ pub(crate) enum ArchiveType {
 }
 
 pub(crate) fn archive_type(path: &Path) -> ArchiveType {
-    match path.extension().and_then(OsStr::to_str) {
-        Some("tab") => ArchiveType::File,
-        Some("bl" | "blz" | "ee" | "eez" | "fl" | "flz" | "nl" | "nlz") => ArchiveType::Stream,
-        _ => ArchiveType::Unknown,
+    match ArchiveKind::from_path(path) {
+        Some(ArchiveKind::Table) => ArchiveType::File,
+        Some(ArchiveKind::Stream) => ArchiveType::Stream,
+        None => ArchiveType::Unknown,
     }
 }
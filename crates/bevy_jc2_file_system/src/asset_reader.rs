@@ -9,10 +9,13 @@ use async_fs::{read_dir, File};
 use bevy::asset::io::{AssetReader, AssetReaderError, ErasedAssetReader, PathStream, Reader};
 use futures_io::{AsyncRead, AsyncSeek, SeekFrom};
 use futures_lite::{future::yield_now, io::Cursor, AsyncReadExt, AsyncSeekExt, Stream, StreamExt};
-use jc2_hashing::HashString;
+use jc2_file_formats::archive::{
+    ArchivePath, ArchivePathError, ArchiveTableEntry, StreamArchiveSlice,
+};
 
 use crate::{
     archive::{archive_type, ArchiveEntry, ArchiveType},
+    overlay::{candidates, find_in_directories, Candidate},
     FileSystemMountsData,
 };
 
@@ -42,15 +45,40 @@ impl FileSystemAssetReader {
     }
 
     async fn read(&self, path: &Path) -> Result<FileReader, AssetReaderError> {
-        // Attempt to get a reader from mounted directories, using the full path
-        for directory in self.mounts.directories.read().await.iter() {
-            let file = directory.join(path);
-            if file.is_file() {
-                return Ok(FileReader::from(File::open(file).await?));
+        // Archives packed inside other archives are read through the outermost entry
+        let Some(nested) = ArchivePath::parse(path).filter(|nested| nested.entries.len() > 1)
+        else {
+            return self.read_file(path).await;
+        };
+
+        let mut bytes = Vec::new();
+        self.read_file(&nested.outer())
+            .await?
+            .read_to_end(&mut bytes)
+            .await?;
+        match nested.extract_inner(bytes) {
+            Ok(bytes) => Ok(FileReader::from(Cursor::new(bytes))),
+            Err(ArchivePathError::Io(error)) => Err(error.into()),
+            Err(_) => Err(AssetReaderError::NotFound(path.into())),
+        }
+    }
+
+    async fn read_file(&self, path: &Path) -> Result<FileReader, AssetReaderError> {
+        // Files in directories that no archive can shadow are read without waiting for archives
+        let max_archive_priority = self.mounts.max_archive_priority.load(Ordering::Relaxed);
+        let unshadowed = match find_in_directories(&*self.mounts.directories.read().await, path) {
+            Some(Candidate::Directory { layer, file })
+                if layer.priority >= max_archive_priority =>
+            {
+                Some(file)
             }
+            _ => None,
+        };
+        if let Some(file) = unshadowed {
+            return Ok(FileReader::from(File::open(file).await?));
         }
 
-        // If the file does not come from a mounted directory, then we must wait for archives
+        // Otherwise we must wait for archives
         match archive_type(path) {
             ArchiveType::Stream => {
                 while self.mounts.pending_archives.load(Ordering::Relaxed) > 0 {
@@ -74,52 +102,47 @@ impl FileSystemAssetReader {
             ArchiveType::File => {}
         }
 
-        // Attempt to get a reader from mounted archives, using only the name
-        if let Some(hash) = path
-            .file_name()
-            .map(|name| HashString::from_bytes(name.as_encoded_bytes()))
-        {
-            for archive in self.mounts.archives.read().await.iter() {
-                // Skip archives that don't contain our hash
-                let Some(entry) = archive.entries.get(&hash) else {
-                    continue;
-                };
+        // Read from whichever layer wins, falling back to the next if it can't be read
+        let source = {
+            let directories = self.mounts.directories.read().await;
+            let archives = self.mounts.archives.read().await;
+            candidates(&directories, &archives, path)
+                .into_iter()
+                .find_map(|candidate| match candidate {
+                    Candidate::Directory { file, .. } => Some(Source::File(file)),
+                    Candidate::Archive { layer, entry } => match entry {
+                        ArchiveEntry::Streamed(streamed) => {
+                            // If the target path is not set, something went very wrong
+                            let Some(target_path) = &layer.value.target_path else {
+                                unreachable!("`archive.target_path` was not set!");
+                            };
 
-                let buffer = match &entry {
-                    ArchiveEntry::Streamed(streamed) => {
-                        // If the target path is not set, something went very wrong
-                        let Some(target_path) = &archive.target_path else {
-                            unreachable!("`archive.target_path` was not set!");
-                        };
-
-                        // Get the archive path from mounted directories
-                        let Some(path) =
-                            self.mounts
-                                .directories
-                                .read()
-                                .await
-                                .iter()
-                                .find_map(|directory| {
-                                    let path = directory.join(target_path);
-                                    path.is_file().then_some(path)
-                                })
-                        else {
-                            // Currently impossible to use bevy_assets::get_base_path() as a fallback...
-                            continue;
-                        };
-
-                        // Open the archive, read the file, and create a cursor
-                        let mut file = File::open(path).await?;
-                        file.seek(SeekFrom::Start(streamed.offset as u64)).await?;
-                        let mut buffer = vec![0u8; streamed.size as usize];
-                        file.read_exact(&mut buffer).await?;
-                        buffer
-                    }
-                    ArchiveEntry::Preloaded(buffer) => buffer.clone(),
-                };
+                            // Get the archive path from mounted directories, though it's currently
+                            // impossible to use bevy_assets::get_base_path() as a fallback...
+                            directories.values().find_map(|directory| {
+                                let path = directory.join(target_path);
+                                path.is_file()
+                                    .then(|| Source::Streamed(path, streamed.clone()))
+                            })
+                        }
+                        ArchiveEntry::Preloaded(slice) => Some(Source::Preloaded(slice.clone())),
+                    },
+                })
+        };
 
-                return Ok(FileReader::from(Cursor::new(buffer)));
-            }
+        if let Some(source) = source {
+            return Ok(match source {
+                Source::File(file) => FileReader::from(File::open(file).await?),
+                Source::Streamed(path, streamed) => {
+                    // Open the archive, read the file, and create a cursor
+                    let mut file = File::open(path).await?;
+                    file.seek(SeekFrom::Start(streamed.offset as u64)).await?;
+                    let mut buffer = vec![0u8; streamed.size as usize];
+                    file.read_exact(&mut buffer).await?;
+                    FileReader::from(Cursor::new(buffer))
+                }
+                Source::Preloaded(slice) => FileReader::from(Cursor::new(slice)),
+            });
         }
 
         // Nothing found
@@ -128,7 +151,7 @@ impl FileSystemAssetReader {
 
     async fn is_directory(&self, path: &Path) -> bool {
         let folder = path.join("");
-        for directory in self.mounts.directories.read().await.iter() {
+        for directory in self.mounts.directories.read().await.values() {
             let file = directory.join(folder.clone());
             if file.is_dir() {
                 return true;
@@ -140,7 +163,7 @@ impl FileSystemAssetReader {
     async fn read_directory(&self, path: &Path) -> Result<DirReader, AssetReaderError> {
         if self.is_directory(path).await {
             let mut paths = Vec::new();
-            for directory in self.mounts.directories.read().await.iter() {
+            for directory in self.mounts.directories.read().await.values() {
                 if let Ok(read_dir) = read_dir(&directory.join(path)).await {
                     let root_path = directory.clone();
                     let mapped_stream: Vec<PathBuf> = read_dir
@@ -173,6 +196,13 @@ impl FileSystemAssetReader {
     }
 }
 
+/// Where the winning copy of a file is read from.
+enum Source {
+    File(PathBuf),
+    Streamed(PathBuf, ArchiveTableEntry),
+    Preloaded(StreamArchiveSlice),
+}
+
 struct FileReader<'a>(Box<Reader<'a>>);
 
 impl<'a> From<File> for FileReader<'a> {
@@ -187,6 +217,12 @@ impl<'a> From<Cursor<Vec<u8>>> for FileReader<'a> {
     }
 }
 
+impl<'a> From<Cursor<StreamArchiveSlice>> for FileReader<'a> {
+    fn from(value: Cursor<StreamArchiveSlice>) -> Self {
+        Self(Box::new(value))
+    }
+}
+
 impl<'a> AsyncRead for FileReader<'a> {
     fn poll_read(
         self: Pin<&mut Self>,
@@ -2,15 +2,15 @@ use bevy::{
     asset::{AssetLoader, AsyncReadExt},
     prelude::*,
 };
-use jc2_hashing::HashList;
+use jc2_hashing::{HashList, HashListError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub(crate) enum FileListError {
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
-    #[error("hash collision: {path:?}")]
-    HashCollision { path: String },
+    #[error("invalid file list: {0}")]
+    HashList(#[from] HashListError),
 }
 
 #[derive(Asset, Debug, Clone, TypePath)]
@@ -35,12 +35,8 @@ impl AssetLoader for FileListLoader {
         let mut buffer = String::new();
         reader.read_to_string(&mut buffer).await?;
 
-        let mut paths = HashList::new();
-        for path in buffer.lines() {
-            if paths.insert_path(path).is_some() {
-                return Err(FileListError::HashCollision { path: path.into() });
-            }
-        }
+        // Same-named files in different directories share a hash, so every path is kept
+        let paths = HashList::read(buffer.as_bytes())?;
 
         Ok(FileList { paths })
     }
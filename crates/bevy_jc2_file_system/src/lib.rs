@@ -7,11 +7,12 @@ use bevy::{
     prelude::*,
     utils::HashMap,
 };
+use jc2_file_formats::archive::Overlay;
 use jc2_hashing::HashString;
 use std::{
     path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicI32, AtomicUsize, Ordering},
         Arc,
     },
 };
@@ -24,6 +25,10 @@ use archive::{archive_type, Archive, ArchiveLoader, ArchiveType};
 mod asset_reader;
 use asset_reader::FileSystemAssetReader;
 
+mod overlay;
+use overlay::candidates;
+pub use overlay::FileSystemLayer;
+
 #[cfg(feature = "tree")]
 mod tree;
 #[cfg(feature = "tree")]
@@ -51,27 +56,51 @@ pub enum FileSystemEvent {
 pub struct FileSystemMountsData {
     pub(crate) pending_archives: AtomicUsize,
     pub(crate) pending_stream_archives: AtomicUsize,
-    pub(crate) directories: RwLock<Vec<PathBuf>>,
-    pub(crate) archives: RwLock<Vec<Archive>>,
+    /// Highest priority of any archive mounted or pending, so files found in directories of at
+    /// least this priority don't have to wait for archives.
+    pub(crate) max_archive_priority: AtomicI32,
+    pub(crate) directories: RwLock<Overlay<PathBuf>>,
+    pub(crate) archives: RwLock<Overlay<Archive>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct PendingArchive {
+    /// Held so the archive keeps loading until it's mounted or cancelled.
+    pub(crate) _handle: Handle<Archive>,
+    pub(crate) priority: i32,
 }
 
 #[derive(Resource, Default, Debug)]
 pub struct FileSystemMounts {
     pub(crate) mounts: Arc<FileSystemMountsData>,
-    pub(crate) pending_archives: HashMap<HashString, Handle<Archive>>,
+    pub(crate) pending_archives: HashMap<HashString, PendingArchive>,
     pub(crate) pending_events: Vec<FileSystemEvent>,
     #[cfg(feature = "tree")]
     pub file_tree: FileSystemTree,
 }
 
 impl FileSystemMounts {
+    /// Mounts a directory with a priority of 0.
     pub fn mount_directory(&mut self, path: impl AsRef<Path>) -> &Self {
+        self.mount_directory_with_priority(path, 0)
+    }
+
+    /// Mounts a directory, shadowing files of layers with a lower priority. Remounting a
+    /// directory changes its priority.
+    ///
+    /// Directories win over archives of the same priority, and between two directories of the
+    /// same priority, the one mounted first wins.
+    pub fn mount_directory_with_priority(
+        &mut self,
+        path: impl AsRef<Path>,
+        priority: i32,
+    ) -> &Self {
         let path: PathBuf = path.as_ref().into();
         {
             let mut directories = self.mounts.directories.write_blocking();
             let directory_count = directories.len();
             directories.retain(|directory| *directory != path);
-            directories.push(path.clone());
+            directories.insert(priority, path.clone());
             if directories.len() > directory_count {
                 self.pending_events
                     .push(FileSystemEvent::DirectoryMounted { path });
@@ -120,7 +149,21 @@ impl FileSystemMounts {
         self
     }
 
+    /// Mounts an archive with a priority of 0.
     pub fn mount_archive(&mut self, asset_server: &AssetServer, path: impl AsRef<Path>) -> &Self {
+        self.mount_archive_with_priority(asset_server, path, 0)
+    }
+
+    /// Mounts an archive, such as a patch or a mod, shadowing entries with the same hash in
+    /// layers with a lower priority.
+    ///
+    /// Between two archives of the same priority, the one mounted first wins.
+    pub fn mount_archive_with_priority(
+        &mut self,
+        asset_server: &AssetServer,
+        path: impl AsRef<Path>,
+        priority: i32,
+    ) -> &Self {
         let path: PathBuf = path.as_ref().into();
         let hash = HashString::from_str(&path.to_string_lossy());
 
@@ -146,9 +189,17 @@ impl FileSystemMounts {
             }
             ArchiveType::Unknown => {}
         }
-
-        self.pending_archives
-            .insert(hash, asset_server.load(path.clone()));
+        self.mounts
+            .max_archive_priority
+            .fetch_max(priority, Ordering::Relaxed);
+
+        self.pending_archives.insert(
+            hash,
+            PendingArchive {
+                _handle: asset_server.load(path.clone()),
+                priority,
+            },
+        );
         self.pending_events
             .push(FileSystemEvent::ArchivePending { path });
         self
@@ -173,11 +224,11 @@ impl FileSystemMounts {
                             self.file_tree.remove(&path);
                         }
                         ArchivePaths::HashList(paths) => {
-                            for file in std::mem::take(paths)
-                                .into_values()
-                                .filter_map(|e| e.into_path())
-                            {
-                                self.file_tree.remove(&path.join(&file));
+                            let paths = std::mem::take(paths);
+                            for (_, file) in paths.iter_all() {
+                                if let Some(file) = file.as_path() {
+                                    self.file_tree.remove(&path.join(file));
+                                }
                             }
                         }
                     }
@@ -211,9 +262,25 @@ impl FileSystemMounts {
         self.mounts
             .archives
             .read_blocking()
-            .iter()
+            .values()
             .any(|archive| archive.hash == hash)
     }
+
+    /// The mounted directory or archive whose copy of `path` is read, if any holds it.
+    pub fn resolve(&self, path: impl AsRef<Path>) -> Option<FileSystemLayer> {
+        self.resolve_all(path).into_iter().next()
+    }
+
+    /// Every mounted directory and archive holding `path`, with the one whose copy is read first
+    /// and those it shadows after.
+    pub fn resolve_all(&self, path: impl AsRef<Path>) -> Vec<FileSystemLayer> {
+        let directories = self.mounts.directories.read_blocking();
+        let archives = self.mounts.archives.read_blocking();
+        candidates(&directories, &archives, path.as_ref())
+            .iter()
+            .map(FileSystemLayer::from)
+            .collect()
+    }
 }
 
 impl Plugin for FileSystemPlugin {
@@ -294,7 +361,7 @@ fn process_archive_events(
         let hash = archive.hash;
 
         // Validate that the archive load wasn't cancelled
-        if !mounts.pending_archives.contains_key(&hash) {
+        let Some(priority) = mounts.pending_archives.get(&hash).map(|p| p.priority) else {
             process(mounts.as_mut(), &archive.source_path, true);
             continue;
         };
@@ -305,7 +372,7 @@ fn process_archive_events(
                 .mounts
                 .directories
                 .read_blocking()
-                .iter()
+                .values()
                 .any(|directory| directory.join(target_path).is_file());
             if !exists {
                 process(mounts.as_mut(), &archive.source_path, true);
@@ -326,18 +393,29 @@ fn process_archive_events(
                 continue;
             };
 
+            // Every candidate location is shown, as colliding names can not be told apart
             for hash in archive.entries.keys() {
-                mounts.file_tree.insert(&match paths.find_path(*hash) {
-                    Some(path) => archive.source_path.join(path),
-                    None => archive
-                        .source_path
-                        .join(&PathBuf::from(hash.hash().to_string())),
-                });
+                let mut found = false;
+                for path in paths.find_all_paths(*hash) {
+                    mounts.file_tree.insert(&archive.source_path.join(path));
+                    found = true;
+                }
+                if !found {
+                    mounts.file_tree.insert(
+                        &archive
+                            .source_path
+                            .join(PathBuf::from(hash.hash().to_string())),
+                    );
+                }
             }
             mounts.file_tree.sort();
         }
 
-        mounts.mounts.archives.write_blocking().push(archive);
+        mounts
+            .mounts
+            .archives
+            .write_blocking()
+            .insert(priority, archive);
         process(mounts.as_mut(), &path, false);
     }
 
@@ -0,0 +1,109 @@
+use std::path::{Path, PathBuf};
+
+use jc2_file_formats::archive::{ArchivePath, Overlay, OverlayLayer};
+use jc2_hashing::HashString;
+
+use crate::archive::{Archive, ArchiveEntry};
+
+/// A mounted directory or archive holding a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FileSystemLayer {
+    Directory { path: PathBuf, priority: i32 },
+    Archive { path: PathBuf, priority: i32 },
+}
+
+impl FileSystemLayer {
+    #[inline]
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Directory { path, .. } | Self::Archive { path, .. } => path,
+        }
+    }
+
+    #[inline]
+    pub fn priority(&self) -> i32 {
+        match self {
+            Self::Directory { priority, .. } | Self::Archive { priority, .. } => *priority,
+        }
+    }
+}
+
+pub(crate) enum Candidate<'a> {
+    Directory {
+        layer: &'a OverlayLayer<PathBuf>,
+        file: PathBuf,
+    },
+    Archive {
+        layer: &'a OverlayLayer<Archive>,
+        entry: &'a ArchiveEntry,
+    },
+}
+
+impl From<&Candidate<'_>> for FileSystemLayer {
+    fn from(value: &Candidate<'_>) -> Self {
+        match value {
+            Candidate::Directory { layer, .. } => Self::Directory {
+                path: layer.value.clone(),
+                priority: layer.priority,
+            },
+            Candidate::Archive { layer, .. } => Self::Archive {
+                path: layer.value.source_path.clone(),
+                priority: layer.priority,
+            },
+        }
+    }
+}
+
+/// Finds the first directory holding `path`, which wins over the others.
+pub(crate) fn find_in_directories<'a>(
+    directories: &'a Overlay<PathBuf>,
+    path: &Path,
+) -> Option<Candidate<'a>> {
+    directories.iter().find_map(|layer| {
+        let file = layer.value.join(path);
+        file.is_file()
+            .then_some(Candidate::Directory { layer, file })
+    })
+}
+
+/// Every layer holding `path`, with the one that wins first.
+///
+/// Directories are searched by the full path and archives by file name only. A directory wins
+/// over an archive of the same priority.
+pub(crate) fn candidates<'a>(
+    directories: &'a Overlay<PathBuf>,
+    archives: &'a Overlay<Archive>,
+    path: &Path,
+) -> Vec<Candidate<'a>> {
+    // Archives packed inside other archives are found through the outermost entry
+    let path = match ArchivePath::parse(path) {
+        Some(nested) if nested.entries.len() > 1 => nested.outer(),
+        _ => path.to_path_buf(),
+    };
+
+    let mut result: Vec<_> = directories
+        .iter()
+        .filter_map(|layer| {
+            let file = layer.value.join(&path);
+            file.is_file()
+                .then_some(Candidate::Directory { layer, file })
+        })
+        .collect();
+
+    if let Some(hash) = path
+        .file_name()
+        .map(|name| HashString::from_bytes(name.as_encoded_bytes()))
+    {
+        for layer in archives.iter() {
+            let Some(entry) = layer.value.entries.get(&hash) else {
+                continue;
+            };
+            let index = result.partition_point(|candidate| match candidate {
+                Candidate::Directory { layer: other, .. } => other.priority >= layer.priority,
+                Candidate::Archive { .. } => true,
+            });
+            result.insert(index, Candidate::Archive { layer, entry });
+        }
+    }
+    result
+}
@@ -23,7 +23,7 @@ pub mod materials;
 #[derive(Error, Debug)]
 pub enum RenderBlockModelError {
     #[error("unsupported render block")]
-    UnsupportedRenderBlock { block: rbm::RenderBlock },
+    UnsupportedRenderBlock { block: Box<rbm::RenderBlock> },
     #[error("unsupported primitive type")]
     UnsupportedPrimitive { primitive: rbm::PrimitiveType },
     #[error("invalid rbm file: {0}")]
@@ -75,6 +75,136 @@ fn get_primitive_topology(
     }
 }
 
+/// Builds a bevy mesh out of the geometry of a block.
+fn create_mesh(generic: &rbm::GenericMesh<'_>) -> Result<Mesh, RenderBlockModelError> {
+    let mut mesh = Mesh::new(
+        get_primitive_topology(generic.primitive_type)?,
+        RenderAssetUsages::default(),
+    );
+
+    // The material reads every attribute, so those a block lacks get neutral values
+    let vertex_count = generic.vertices.len();
+
+    macro_rules! vec_attr {
+        ($mesh:ident, $attribute:expr, $vec:ty, $values:expr, $default:expr) => {
+            $mesh.insert_attribute(
+                $attribute,
+                match $values {
+                    Some(values) => values
+                        .into_iter()
+                        .map(|value| <$vec>::from_array(value.into()))
+                        .collect::<Vec<$vec>>(),
+                    None => vec![$default; vertex_count],
+                },
+            )
+        };
+    }
+
+    mesh.insert_indices(Indices::U16(generic.indices.to_vec()));
+
+    vec_attr!(
+        mesh,
+        Mesh::ATTRIBUTE_POSITION,
+        Vec3,
+        Some(generic.positions()),
+        Vec3::ZERO
+    );
+    vec_attr!(mesh, Mesh::ATTRIBUTE_UV_0, Vec2, generic.uvs(0), Vec2::ZERO);
+    vec_attr!(mesh, Mesh::ATTRIBUTE_UV_1, Vec2, generic.uvs(1), Vec2::ZERO);
+    vec_attr!(
+        mesh,
+        Mesh::ATTRIBUTE_NORMAL,
+        Vec3,
+        generic.normals(),
+        Vec3::Y
+    );
+    vec_attr!(
+        mesh,
+        Mesh::ATTRIBUTE_TANGENT,
+        Vec4,
+        generic.tangents(),
+        Vec4::new(1.0, 0.0, 0.0, 1.0)
+    );
+    vec_attr!(
+        mesh,
+        Mesh::ATTRIBUTE_COLOR,
+        Vec4,
+        generic.colors(),
+        Vec4::ONE
+    );
+
+    Ok(mesh)
+}
+
+fn load_image(
+    load_context: &mut LoadContext,
+    path: impl Into<PathBuf>,
+    is_srgb: bool,
+) -> Handle<Image> {
+    load_context
+        .loader()
+        .with_settings(move |settings: &mut ImageLoaderSettings| {
+            settings.is_srgb = is_srgb;
+            settings.sampler = ImageSampler::Descriptor(
+                SamplerDescriptor {
+                    address_mode_u: AddressMode::Repeat,
+                    address_mode_v: AddressMode::Repeat,
+                    address_mode_w: AddressMode::Repeat,
+                    mag_filter: FilterMode::Linear,
+                    min_filter: FilterMode::Linear,
+                    mipmap_filter: FilterMode::Linear,
+                    anisotropy_clamp: 16,
+                    lod_min_clamp: 0.0,
+                    lod_max_clamp: 0.0,
+                    ..default()
+                }
+                .into(),
+            );
+        })
+        .load(path.into())
+}
+
+/// Builds the material of a block, loading its textures relative to the model.
+fn create_material(
+    block: &rbm::RenderBlock,
+    generic: &rbm::GenericMesh<'_>,
+    load_context: &mut LoadContext,
+) -> RenderBlockGeneralMaterial {
+    let parent = if let Some(parent) = load_context.path().parent() {
+        parent.to_path_buf()
+    } else {
+        load_context.path().into()
+    };
+    let textures = generic.textures;
+
+    // Blocks other than general ones are drawn with the general material for now
+    let mut material = match block {
+        rbm::RenderBlock::General(general) => RenderBlockGeneralMaterial::from(&general.attributes),
+        _ => RenderBlockGeneralMaterial::from(&rbm::GeneralAttributes::default()),
+    };
+
+    // The mesh already has the scale and extents of the block applied
+    material.scale = 1.0;
+    material.uv0_scale = Vec2::ONE;
+    material.uv1_scale = Vec2::ONE;
+
+    for (index, texture, is_srgb) in [
+        (0, &mut material.diffuse_texture, true),
+        (1, &mut material.normal_texture, false),
+        (2, &mut material.properties_texture, false),
+    ] {
+        if !textures[index].is_empty() {
+            *texture = Some(load_image(
+                load_context,
+                parent.join(textures[index]),
+                is_srgb,
+            ));
+        }
+    }
+
+    material
+}
+
 impl AssetLoader for RenderBlockLoader {
     type Asset = RenderBlockMesh;
     type Settings = ();
@@ -94,100 +224,21 @@ impl AssetLoader for RenderBlockLoader {
         let mut primitives = Vec::with_capacity(model.blocks.len());
 
         for (idx, block) in model.blocks.iter().enumerate() {
-            match block {
-                rbm::RenderBlock::General(general) => {
-                    let mut mesh = Mesh::new(
-                        get_primitive_topology(general.material.primitive_type)?,
-                        RenderAssetUsages::default(),
-                    );
-
-                    macro_rules! vec_attr {
-                        ($mesh:ident, $attribute:expr, $vec:ty, $block:expr, $field:ident) => {
-                            $mesh.insert_attribute(
-                                $attribute,
-                                $block
-                                    .vertices
-                                    .iter()
-                                    .map(|vertex| <$vec>::from_array(vertex.$field.into()))
-                                    .collect::<Vec<$vec>>(),
-                            )
-                        };
-                    }
-
-                    mesh.insert_indices(Indices::U16(general.indices.to_vec()));
-
-                    vec_attr!(mesh, Mesh::ATTRIBUTE_POSITION, Vec3, general, position);
-                    vec_attr!(mesh, Mesh::ATTRIBUTE_UV_0, Vec2, general, uv0);
-                    vec_attr!(mesh, Mesh::ATTRIBUTE_UV_1, Vec2, general, uv1);
-                    vec_attr!(mesh, Mesh::ATTRIBUTE_NORMAL, Vec3, general, normal);
-                    vec_attr!(mesh, Mesh::ATTRIBUTE_TANGENT, Vec4, general, tangent);
-                    vec_attr!(mesh, Mesh::ATTRIBUTE_COLOR, Vec4, general, color);
-
-                    fn load_image(
-                        load_context: &mut LoadContext,
-                        path: impl Into<PathBuf>,
-                        is_srgb: bool,
-                    ) -> Handle<Image> {
-                        load_context
-                            .loader()
-                            .with_settings(move |settings: &mut ImageLoaderSettings| {
-                                settings.is_srgb = is_srgb;
-                                settings.sampler = ImageSampler::Descriptor(
-                                    SamplerDescriptor {
-                                        address_mode_u: AddressMode::Repeat,
-                                        address_mode_v: AddressMode::Repeat,
-                                        address_mode_w: AddressMode::Repeat,
-                                        mag_filter: FilterMode::Linear,
-                                        min_filter: FilterMode::Linear,
-                                        mipmap_filter: FilterMode::Linear,
-                                        anisotropy_clamp: 16,
-                                        lod_min_clamp: 0.0,
-                                        lod_max_clamp: 0.0,
-                                        ..default()
-                                    }
-                                    .into(),
-                                );
-                            })
-                            .load(path.into())
-                    }
-
-                    let parent = if let Some(parent) = load_context.path().parent() {
-                        parent.to_path_buf()
-                    } else {
-                        load_context.path().into()
-                    };
-                    let textures = &general.material.textures;
-
-                    let mut material = RenderBlockGeneralMaterial::from(&general.attributes);
-                    material.diffuse_texture = Some(load_image(
-                        load_context,
-                        parent.join(textures[0].as_ref()),
-                        true,
-                    ));
-                    material.normal_texture = Some(load_image(
-                        load_context,
-                        parent.join(textures[1].as_ref()),
-                        false,
-                    ));
-                    material.properties_texture = Some(load_image(
-                        load_context,
-                        parent.join(textures[2].as_ref()),
-                        false,
-                    ));
-
-                    let mesh = load_context.add_labeled_asset(format!("Mesh{idx:?}"), mesh);
-                    let material = load_context
-                        .add_labeled_asset(format!("Material{idx:?}"), material)
-                        .into();
-
-                    primitives.push(RenderBlockPrimitive { mesh, material });
-                }
-                _ => {
-                    return Err(RenderBlockModelError::UnsupportedRenderBlock {
-                        block: block.clone(),
-                    })
-                }
-            }
+            let Some(generic) = block.as_mesh() else {
+                return Err(RenderBlockModelError::UnsupportedRenderBlock {
+                    block: Box::new(block.clone()),
+                });
+            };
+
+            let mesh = create_mesh(&generic)?;
+            let material = create_material(block, &generic, load_context);
+
+            let mesh = load_context.add_labeled_asset(format!("Mesh{idx:?}"), mesh);
+            let material = load_context
+                .add_labeled_asset(format!("Material{idx:?}"), material)
+                .into();
+
+            primitives.push(RenderBlockPrimitive { mesh, material });
         }
 
         Ok(RenderBlockMesh { primitives })
@@ -0,0 +1,194 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io::{Seek, Write},
+};
+
+use jc2_hashing::{HashAlgorithm, HashString, Little32CaseFolded};
+use thiserror::Error;
+
+use super::{ArchiveEndian, ArchiveTable, ArchiveTableEntry};
+
+/// Block size every file in an `.arc` starts on, also stored as the `.tab` header.
+pub const ARCHIVE_ALIGNMENT: usize = 0x800;
+
+/// Hash an archive table stores for `name`, which is its file name with ASCII case folded.
+#[inline]
+pub(crate) fn entry_hash(name: &str) -> HashString {
+    let file_name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    Little32CaseFolded::hash_str(file_name)
+}
+
+#[derive(Error, Debug)]
+pub enum ArchiveBuilderError {
+    #[error("invalid file: {0}")]
+    Binrw(#[from] binrw::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{name:?} hashes the same as {existing:?} but has different contents")]
+    HashCollision { name: String, existing: String },
+    #[error("archive exceeds the maximum size of 4 GiB")]
+    TooLarge,
+}
+
+#[derive(Clone, Debug)]
+struct ArchiveBuilderFile {
+    names: Vec<String>,
+    data: Vec<u8>,
+}
+
+/// Lays out named files into a new `.arc`, alongside the matching `.tab` and `.filelist`.
+///
+/// Names are paths inside the archive such as `models\jc_characters\rico.rbm`. Entries are keyed
+/// by the hash of their lowercased file name, so files sharing a name must share their contents.
+#[derive(Clone, Debug, Default)]
+pub struct ArchiveBuilder {
+    endian: ArchiveEndian,
+    dedup: bool,
+    hashes: HashMap<HashString, usize>,
+    files: Vec<ArchiveBuilderFile>,
+}
+
+impl ArchiveBuilder {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_endian(mut self, endian: ArchiveEndian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    /// Stores identical files once, with every entry holding them pointing at the same offset.
+    #[inline]
+    pub fn with_dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<HashString, ArchiveBuilderError> {
+        let name: String = name.into();
+        let data: Vec<u8> = data.into();
+        let hash = entry_hash(&name);
+
+        match self.hashes.entry(hash) {
+            Entry::Occupied(entry) => {
+                let file = &mut self.files[*entry.get()];
+                if file.data != data {
+                    return Err(ArchiveBuilderError::HashCollision {
+                        name,
+                        existing: file.names[0].clone(),
+                    });
+                }
+                if !file.names.contains(&name) {
+                    file.names.push(name);
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(self.files.len());
+                self.files.push(ArchiveBuilderFile {
+                    names: vec![name],
+                    data,
+                });
+            }
+        }
+        Ok(hash)
+    }
+
+    /// Builds the table, calling `write_data` with each file's data followed by the padding up to
+    /// the next [`ARCHIVE_ALIGNMENT`] boundary, in order of offset.
+    fn layout<F>(&self, mut write_data: F) -> Result<ArchiveTable, ArchiveBuilderError>
+    where
+        F: FnMut(&[u8], usize) -> Result<(), ArchiveBuilderError>,
+    {
+        let mut entries = HashMap::with_capacity(self.files.len());
+        let mut placed: HashMap<&[u8], ArchiveTableEntry> = HashMap::new();
+        let mut offset = 0usize;
+        for (hash, &index) in self.sorted_hashes() {
+            let data = &self.files[index].data;
+            if let Some(shared) = placed.get(data.as_slice()) {
+                entries.insert(*hash, shared.clone());
+                continue;
+            }
+            let padding = data.len().next_multiple_of(ARCHIVE_ALIGNMENT) - data.len();
+            let (Ok(entry_offset), Ok(size)) = (u32::try_from(offset), u32::try_from(data.len()))
+            else {
+                return Err(ArchiveBuilderError::TooLarge);
+            };
+            let entry = ArchiveTableEntry {
+                offset: entry_offset,
+                size,
+            };
+            if self.dedup {
+                placed.insert(data, entry.clone());
+            }
+            entries.insert(*hash, entry);
+            write_data(data, padding)?;
+            offset += data.len() + padding;
+        }
+        Ok(ArchiveTable {
+            endian: self.endian,
+            entries,
+        })
+    }
+
+    #[inline]
+    fn sorted_hashes(&self) -> Vec<(&HashString, &usize)> {
+        let mut hashes: Vec<_> = self.hashes.iter().collect();
+        hashes.sort_unstable_by_key(|(hash, _)| **hash);
+        hashes
+    }
+
+    /// Table describing where each file will be placed, without writing any data.
+    pub fn table(&self) -> Result<ArchiveTable, ArchiveBuilderError> {
+        self.layout(|_, _| Ok(()))
+    }
+
+    /// Every inserted name, one per line, in the same format as the shipped `.filelist` files.
+    pub fn file_list(&self) -> String {
+        let mut names: Vec<_> = self
+            .files
+            .iter()
+            .flat_map(|file| file.names.iter())
+            .collect();
+        names.sort_unstable();
+        names.into_iter().fold(String::new(), |mut result, name| {
+            result.push_str(name);
+            result.push('\n');
+            result
+        })
+    }
+
+    /// Writes the `.arc` data, then the `.tab` describing it and the `.filelist` naming it.
+    pub fn write<A: Write, T: Write + Seek, F: Write>(
+        &self,
+        arc: &mut A,
+        tab: &mut T,
+        file_list: &mut F,
+    ) -> Result<ArchiveTable, ArchiveBuilderError> {
+        let padding = [0u8; ARCHIVE_ALIGNMENT];
+        let table = self.layout(|data, length| {
+            arc.write_all(data)?;
+            arc.write_all(&padding[..length])?;
+            Ok(())
+        })?;
+        table.write(tab)?;
+        file_list.write_all(self.file_list().as_bytes())?;
+        Ok(table)
+    }
+}
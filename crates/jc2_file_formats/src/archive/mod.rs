@@ -1,6 +1,8 @@
 use std::{
     collections::HashMap,
+    ffi::OsStr,
     io::{Read, Seek, Write},
+    path::Path,
 };
 
 use binrw::{binrw, parser, writer, BinRead, BinResult, BinWrite};
@@ -8,9 +10,25 @@ use jc2_hashing::HashString;
 
 use crate::string::LengthString;
 
+mod builder;
+pub use builder::*;
+
+mod nested;
+pub use nested::*;
+
+mod overlay;
+pub use overlay::*;
+
+mod stream_index;
+pub use stream_index::*;
+
+mod verify;
+pub use verify::*;
+
 #[binrw]
 #[repr(C)]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ArchiveTableEntry {
     pub offset: u32,
     pub size: u32,
@@ -18,6 +36,7 @@ pub struct ArchiveTableEntry {
 
 #[binrw]
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ArchiveEndian {
     #[default]
     #[brw(magic = b"\x00\x08\x00\x00")]
@@ -26,8 +45,26 @@ pub enum ArchiveEndian {
     Big,
 }
 
+impl ArchiveEndian {
+    /// Peeks at the magic of an archive table, leaving `reader` where it was.
+    pub fn detect<R: Read + Seek>(reader: &mut R) -> BinResult<Self> {
+        crate::peek(reader, Self::read_ne)
+    }
+}
+
+impl From<ArchiveEndian> for binrw::Endian {
+    #[inline]
+    fn from(value: ArchiveEndian) -> Self {
+        match value {
+            ArchiveEndian::Little => Self::Little,
+            ArchiveEndian::Big => Self::Big,
+        }
+    }
+}
+
 #[binrw]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ArchiveTable {
     pub endian: ArchiveEndian,
     #[br(parse_with = Self::parse_entries)]
@@ -37,20 +74,29 @@ pub struct ArchiveTable {
 }
 
 impl ArchiveTable {
+    /// Reads a table in either endianness, detected from its magic.
     pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, binrw::Error> {
-        #[cfg(target_endian = "little")]
-        return Self::read_le(reader);
+        Self::read_ne(reader)
+    }
 
-        #[cfg(target_endian = "big")]
-        return Self::read_be(reader);
+    /// Reads a table, failing if it was not written with `endian`.
+    pub fn read_endian<R: Read + Seek>(
+        reader: &mut R,
+        endian: ArchiveEndian,
+    ) -> Result<Self, binrw::Error> {
+        crate::expect_endian(reader, endian, |table: &Self| table.endian, Self::read)
     }
 
+    /// Writes the table with the endianness in [`ArchiveTable::endian`].
     pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<(), binrw::Error> {
-        #[cfg(target_endian = "little")]
-        return self.write_le(writer);
+        self.write_options(writer, self.endian.into(), ())
+    }
 
-        #[cfg(target_endian = "big")]
-        return self.write_be(writer);
+    /// Converts the table to be written for the platform using `endian`.
+    #[inline]
+    pub fn with_endian(mut self, endian: ArchiveEndian) -> Self {
+        self.endian = endian;
+        self
     }
 
     #[parser(reader, endian)]
@@ -74,6 +120,8 @@ impl ArchiveTable {
 
     #[writer(writer, endian)]
     fn write_entries(entries: &HashMap<HashString, ArchiveTableEntry>) -> BinResult<()> {
+        let mut entries: Vec<_> = entries.iter().collect();
+        entries.sort_unstable_by_key(|(hash, _)| **hash);
         for (hash, entry) in entries {
             hash.write_options(writer, endian, ())?;
             entry.write_options(writer, endian, ())?;
@@ -84,6 +132,7 @@ impl ArchiveTable {
 
 #[binrw]
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StreamArchiveEndian {
     #[default]
     #[brw(magic = b"\x04\x00\x00\x00SARC")]
@@ -92,27 +141,167 @@ pub enum StreamArchiveEndian {
     Big,
 }
 
+impl StreamArchiveEndian {
+    /// Peeks at the magic of an uncompressed stream archive, leaving `reader` where it was.
+    pub fn detect<R: Read + Seek>(reader: &mut R) -> BinResult<Self> {
+        crate::peek(reader, Self::read_ne)
+    }
+}
+
+impl From<StreamArchiveEndian> for binrw::Endian {
+    #[inline]
+    fn from(value: StreamArchiveEndian) -> Self {
+        match value {
+            StreamArchiveEndian::Little => Self::Little,
+            StreamArchiveEndian::Big => Self::Big,
+        }
+    }
+}
+
 #[binrw]
 #[brw(repr = u32)]
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StreamArchiveVersion {
     #[default]
     V2 = 2,
 }
 
-#[binrw]
+/// Entries are aligned to this many bytes unless padding was preserved from the source.
+pub const STREAM_ARCHIVE_ALIGNMENT: usize = 4;
+
+/// The table of contents is padded to this many bytes unless padding was preserved from the
+/// source.
+pub const STREAM_ARCHIVE_TABLE_ALIGNMENT: usize = 16;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreamArchiveEntry {
+    pub name: String,
+    pub data: Vec<u8>,
+    /// Zero bytes written after the data, as found in the source archive. `None` pads up to
+    /// [`STREAM_ARCHIVE_ALIGNMENT`].
+    pub padding: Option<u32>,
+}
+
+impl StreamArchiveEntry {
+    #[inline]
+    pub fn new(name: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            name: name.into(),
+            data: data.into(),
+            padding: None,
+        }
+    }
+
+    #[inline]
+    fn padding(&self) -> usize {
+        self.padding.map_or_else(
+            || self.data.len().next_multiple_of(STREAM_ARCHIVE_ALIGNMENT) - self.data.len(),
+            |padding| padding as usize,
+        )
+    }
+}
+
+/// How a stream archive is compressed on disk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StreamArchiveCompression {
+    #[default]
+    None,
+    /// Zlib with a level from 0 (store) to 9 (smallest), higher levels are written as 9
+    Zlib(u32),
+}
+
+impl StreamArchiveCompression {
+    pub const DEFAULT_LEVEL: u32 = 6;
+    pub const MAX_LEVEL: u32 = 9;
+
+    /// Compressed variants of stream archives end in `z`, such as `.blz` for `.bl`. Extensions
+    /// are matched ignoring ASCII case.
+    pub fn from_path(path: &Path) -> Self {
+        let compressed = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .is_some_and(|extension| {
+                ["blz", "eez", "flz", "nlz"]
+                    .iter()
+                    .any(|compressed| extension.eq_ignore_ascii_case(compressed))
+            });
+        if compressed {
+            Self::Zlib(Self::DEFAULT_LEVEL)
+        } else {
+            Self::None
+        }
+    }
+
+    /// Changes the level used if compressed, leaving uncompressed archives as is.
+    #[inline]
+    pub fn with_level(self, level: u32) -> Self {
+        match self {
+            Self::None => Self::None,
+            Self::Zlib(_) => Self::Zlib(level.min(Self::MAX_LEVEL)),
+        }
+    }
+}
+
+/// How entries are laid out when writing a stream archive.
+///
+/// The default layout writes every entry after the previous one, which reproduces archives read
+/// from the game.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StreamArchiveLayout {
+    /// Store identical data once, with every entry holding it pointing at the same offset.
+    pub dedup: bool,
+    /// Alignment of the start of entries by lowercase extension, such as `dds`.
+    pub alignments: HashMap<String, usize>,
+}
+
+impl StreamArchiveLayout {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with_dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Starts entries with `extension` at a multiple of `alignment` bytes into the archive.
+    #[inline]
+    pub fn with_alignment(mut self, extension: &str, alignment: usize) -> Self {
+        self.alignments.insert(
+            extension.trim_start_matches('.').to_lowercase(),
+            alignment.max(1),
+        );
+        self
+    }
+
+    fn alignment(&self, name: &str) -> Option<usize> {
+        let extension = Path::new(name).extension()?.to_str()?.to_lowercase();
+        self.alignments.get(&extension).copied()
+    }
+}
+
+/// A stream archive, keeping entries in the order of the source's table of contents.
+///
+/// Reading and then writing an archive reproduces it byte for byte, as long as its data is laid
+/// out in table order, which is the case for the original game files.
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StreamArchive {
     pub endian: StreamArchiveEndian,
-    #[brw(is_little(matches!(endian, StreamArchiveEndian::Little)))]
     pub version: StreamArchiveVersion,
-    #[br(parse_with = Self::parse_entries)]
-    #[bw(write_with = Self::write_entries)]
-    #[brw(is_little(matches!(endian, StreamArchiveEndian::Little)))]
-    pub entries: HashMap<String, Vec<u8>>,
+    /// Zero bytes written after the table of contents, as found in the source archive. `None`
+    /// pads up to [`STREAM_ARCHIVE_TABLE_ALIGNMENT`].
+    pub table_padding: Option<u32>,
+    pub entries: Vec<StreamArchiveEntry>,
 }
 
 impl StreamArchive {
+    /// Reads an archive in either endianness, detected from its magic, inflating it first if it
+    /// is compressed.
     pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, binrw::Error> {
         let compressed = u8::read_options(reader, binrw::Endian::Little, ())? == 0x78;
         reader.seek(std::io::SeekFrom::Start(0))?;
@@ -120,113 +309,239 @@ impl StreamArchive {
         if compressed {
             let mut buffer = Vec::new();
             flate2::read::ZlibDecoder::new(reader).read_to_end(&mut buffer)?;
-            let mut reader = std::io::Cursor::new(buffer);
-
-            #[cfg(target_endian = "little")]
-            return Self::read_le(&mut reader);
-
-            #[cfg(target_endian = "big")]
-            return Self::read_be(&mut reader);
+            Self::read_ne(&mut std::io::Cursor::new(buffer))
         } else {
-            #[cfg(target_endian = "little")]
-            return Self::read_le(reader);
+            Self::read_ne(reader)
+        }
+    }
 
-            #[cfg(target_endian = "big")]
-            return Self::read_be(reader);
-        };
+    /// Reads an archive, failing if it was not written with `endian`.
+    pub fn read_endian<R: Read + Seek>(
+        reader: &mut R,
+        endian: StreamArchiveEndian,
+    ) -> Result<Self, binrw::Error> {
+        crate::expect_endian(reader, endian, |archive: &Self| archive.endian, Self::read)
     }
 
+    /// Writes the archive with the endianness in [`StreamArchive::endian`].
     pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<(), binrw::Error> {
-        #[cfg(target_endian = "little")]
-        return self.write_le(writer);
-
-        #[cfg(target_endian = "big")]
-        return self.write_be(writer);
+        self.write_options(writer, self.endian.into(), ())
     }
 
-    #[parser(reader, endian)]
-    fn parse_entries() -> BinResult<HashMap<String, Vec<u8>>> {
-        let table_size = u32::read_options(reader, endian, ())?;
-        let table_end = reader.stream_position()? + table_size as u64;
-
-        let mut result = HashMap::with_capacity(16);
-        loop {
-            // We read until the table can not contain at least one empty entry
-            if (table_end - reader.stream_position()?) < 16 {
-                break;
+    /// Writes the archive like [`StreamArchive::write`], laying out entries' data as requested.
+    pub fn write_with_layout<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        layout: &StreamArchiveLayout,
+    ) -> Result<(), binrw::Error> {
+        let endian = binrw::Endian::from(self.endian);
+        self.endian.write_options(writer, endian, ())?;
+        self.version.write_options(writer, endian, ())?;
+
+        // Calculate the size of the table of contents, including its padding
+        let table_used = self.entries.iter().fold(0usize, |size, entry| {
+            size + std::mem::size_of::<u32>() * 3 + entry.name.len()
+        });
+        let table_padding = self.table_padding.map_or_else(
+            || table_used.next_multiple_of(STREAM_ARCHIVE_TABLE_ALIGNMENT) - table_used,
+            |padding| padding as usize,
+        );
+        let table_size = table_used + table_padding;
+        let Ok(table_size) = u32::try_from(table_size) else {
+            return Err(too_large(writer)?);
+        };
+        table_size.write_options(writer, endian, ())?;
+
+        // Place every entry after the previous one, unless it shares data with an earlier entry
+        let data_start = writer.stream_position()? as usize + table_size as usize;
+        let mut offset = data_start;
+        let mut placed: HashMap<&[u8], usize> = HashMap::new();
+        let mut offsets = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            if layout.dedup {
+                if let Some(&shared) = placed.get(entry.data.as_slice()) {
+                    offsets.push((shared, false));
+                    continue;
+                }
+            }
+            if let Some(alignment) = layout.alignment(&entry.name) {
+                offset = offset.next_multiple_of(alignment);
             }
+            offsets.push((offset, true));
+            if layout.dedup {
+                placed.insert(&entry.data, offset);
+            }
+            offset += entry.data.len() + entry.padding();
+        }
 
-            // We read the name and entry
-            let name = LengthString::<u32>::read_options(reader, endian, ())?.into();
-            let entry = ArchiveTableEntry::read_options(reader, endian, ())?;
+        // Write the table of contents
+        for (entry, &(offset, _)) in self.entries.iter().zip(&offsets) {
+            let (Ok(entry_offset), Ok(size)) =
+                (u32::try_from(offset), u32::try_from(entry.data.len()))
+            else {
+                return Err(too_large(writer)?);
+            };
+            LengthString::<u32>::from(entry.name.clone()).write_options(writer, endian, ())?;
+            ArchiveTableEntry {
+                offset: entry_offset,
+                size,
+            }
+            .write_options(writer, endian, ())?;
+        }
+        writer.write_all(&vec![0u8; table_padding])?;
 
-            // Then the data via seeking to it, then returning to where we began
-            let stream_position = reader.stream_position()?;
-            let mut data = vec![0u8; entry.size as usize];
-            reader.seek(std::io::SeekFrom::Start(entry.offset as u64))?;
-            reader.read_exact(&mut data)?;
-            reader.seek(std::io::SeekFrom::Start(stream_position))?;
-            result.insert(name, data);
+        // Finally write the data, padding up to aligned entries
+        let mut position = data_start;
+        for (entry, &(offset, written)) in self.entries.iter().zip(&offsets) {
+            if !written {
+                continue;
+            }
+            writer.write_all(&vec![0u8; offset - position])?;
+            writer.write_all(&entry.data)?;
+            writer.write_all(&vec![0u8; entry.padding()])?;
+            position = offset + entry.data.len() + entry.padding();
         }
 
-        Ok(result)
+        Ok(())
     }
 
-    #[writer(writer, endian)]
-    fn write_entries(entries: &HashMap<String, Vec<u8>>) -> BinResult<()> {
-        // Small helper to align to next power of two
-        #[inline(always)]
-        const fn align(value: usize, alignment: usize) -> usize {
-            let align = alignment - 1;
-            (value + align) & !align
+    /// Converts the archive to be written for the platform using `endian`.
+    #[inline]
+    pub fn with_endian(mut self, endian: StreamArchiveEndian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    /// Writes the archive, compressing it as a whole when requested. Unlike
+    /// [`StreamArchive::write`], the writer does not need to be seekable.
+    pub fn write_compressed<W: Write>(
+        &self,
+        writer: &mut W,
+        compression: StreamArchiveCompression,
+    ) -> Result<(), binrw::Error> {
+        self.write_compressed_with_layout(writer, compression, &StreamArchiveLayout::default())
+    }
+
+    /// Writes the archive like [`StreamArchive::write_compressed`], laying out entries' data as
+    /// requested.
+    pub fn write_compressed_with_layout<W: Write>(
+        &self,
+        writer: &mut W,
+        compression: StreamArchiveCompression,
+        layout: &StreamArchiveLayout,
+    ) -> Result<(), binrw::Error> {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        self.write_with_layout(&mut buffer, layout)?;
+        match compression {
+            StreamArchiveCompression::None => writer.write_all(buffer.get_ref())?,
+            StreamArchiveCompression::Zlib(level) => {
+                let level = level.min(StreamArchiveCompression::MAX_LEVEL);
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(writer, flate2::Compression::new(level));
+                encoder.write_all(buffer.get_ref())?;
+                encoder.finish()?;
+            }
         }
+        Ok(())
+    }
 
-        // Calculate the total size necessary to store all files
-        let mut entry_padding: Vec<usize> = Vec::with_capacity(entries.len());
-        let buffer_size = entries.values().fold(0usize, |size, entry| {
-            let entry_size = entry.len();
-            let aligned_entry_size = align(entry_size, 4);
-            entry_padding.push(aligned_entry_size - entry_size);
-            size + aligned_entry_size
-        });
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&StreamArchiveEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
 
-        // Build the final buffer, and our table of contents
-        let mut table = Vec::with_capacity(entries.len());
-        let mut buffer = Vec::with_capacity(buffer_size);
-        for ((name, data), padding) in entries.iter().zip(&entry_padding) {
-            table.push((
-                LengthString::<u32>::from(name.clone()),
-                ArchiveTableEntry {
-                    offset: buffer.len() as u32,
-                    size: data.len() as u32,
-                },
-            ));
-            buffer.extend_from_slice(data);
-            buffer.extend_from_slice(&vec![0u8; *padding]);
+    #[inline]
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut StreamArchiveEntry> {
+        self.entries.iter_mut().find(|entry| entry.name == name)
+    }
+
+    /// Replaces the data of an existing entry in place, or appends a new entry, returning the
+    /// previous data. Replaced entries fall back to the default alignment.
+    pub fn insert(&mut self, name: impl Into<String>, data: impl Into<Vec<u8>>) -> Option<Vec<u8>> {
+        let entry = StreamArchiveEntry::new(name, data);
+        if let Some(existing) = self.get_mut(&entry.name) {
+            existing.padding = None;
+            return Some(std::mem::replace(&mut existing.data, entry.data));
         }
+        self.entries.push(entry);
+        None
+    }
 
-        // Write the table of contents size
-        let (table_size, table_padding) = {
-            let size = table.iter().fold(0usize, |size, entry| {
-                size + entry.0.size() + std::mem::size_of_val(&entry.1)
-            });
-            let padding = align(size, 16) - size;
-            ((size + padding) as u32, padding)
-        };
-        table_size.write_options(writer, endian, ())?;
+    pub fn remove(&mut self, name: &str) -> Option<StreamArchiveEntry> {
+        let index = self.entries.iter().position(|entry| entry.name == name)?;
+        Some(self.entries.remove(index))
+    }
+}
 
-        // Write the table of contents
-        let table_position = writer.stream_position()? as u32;
-        for (name, entry) in &mut table {
-            entry.offset += table_position + table_size;
-            name.write_options(writer, endian, ())?;
-            entry.write_options(writer, endian, ())?;
+impl BinRead for StreamArchive {
+    type Args<'a> = ();
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<Self> {
+        let index = StreamArchiveIndex::read_options(reader, endian, ())?;
+        let stream_end = reader.seek(std::io::SeekFrom::End(0))?;
+
+        let table_used = index.entries.iter().fold(0u64, |size, entry| {
+            size + (std::mem::size_of::<u32>() * 3 + entry.name.len()) as u64
+        });
+        let table_padding = (index.table_size as u64).checked_sub(table_used);
+
+        // Padding is the gap up to the next entry's data, or the end of the archive, which is
+        // only known when data is laid out in table order
+        let ends: Vec<u64> = index
+            .entries
+            .iter()
+            .skip(1)
+            .map(|entry| entry.offset as u64)
+            .chain(std::iter::once(stream_end))
+            .collect();
+
+        let mut entries = Vec::with_capacity(index.entries.len());
+        for (entry, end) in index.entries.into_iter().zip(ends) {
+            let offset = entry.offset as u64;
+            let padding = end
+                .checked_sub(offset + entry.size as u64)
+                .and_then(|padding| u32::try_from(padding).ok());
+
+            let mut data = vec![0u8; entry.size as usize];
+            reader.seek(std::io::SeekFrom::Start(offset))?;
+            reader.read_exact(&mut data)?;
+            entries.push(StreamArchiveEntry {
+                name: entry.name,
+                data,
+                padding,
+            });
         }
-        writer.write_all(&vec![0u8; table_padding])?;
 
-        // Finally write the data buffer
-        writer.write_all(&buffer)?;
+        Ok(Self {
+            endian: index.endian,
+            version: index.version,
+            table_padding: table_padding.and_then(|padding| u32::try_from(padding).ok()),
+            entries,
+        })
+    }
+}
 
-        Ok(())
+impl BinWrite for StreamArchive {
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        _endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<()> {
+        self.write_with_layout(writer, &StreamArchiveLayout::default())
     }
 }
+
+#[inline]
+fn too_large<S: Seek>(stream: &mut S) -> BinResult<binrw::Error> {
+    Ok(binrw::Error::AssertFail {
+        pos: stream.stream_position()?,
+        message: "stream archive exceeds the maximum size of 4 GiB".into(),
+    })
+}
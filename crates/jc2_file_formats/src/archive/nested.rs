@@ -0,0 +1,195 @@
+use std::{
+    ffi::OsStr,
+    fs::File,
+    io::{BufReader, Cursor, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use jc2_hashing::HashString;
+use thiserror::Error;
+
+use super::{ArchiveTable, StreamArchiveIndex, StreamArchiveIndexEntry};
+
+#[derive(Error, Debug)]
+pub enum ArchivePathError {
+    #[error("invalid file: {0}")]
+    Binrw(#[from] binrw::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{entry:?} not found in {archive:?}")]
+    NotFound { archive: PathBuf, entry: PathBuf },
+    #[error("{path:?} can not contain other files")]
+    NotAnArchive { path: PathBuf },
+}
+
+/// Kind of archive, told by its extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveKind {
+    /// A `.tab` describing the files in the `.arc` next to it.
+    Table,
+    /// A stream archive, compressed if its extension ends in `z`.
+    Stream,
+}
+
+impl ArchiveKind {
+    const STREAM_EXTENSIONS: [&'static str; 8] =
+        ["bl", "blz", "ee", "eez", "fl", "flz", "nl", "nlz"];
+
+    /// Extensions are matched ignoring ASCII case, as the game does.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension().and_then(OsStr::to_str)?;
+        if extension.eq_ignore_ascii_case("tab") {
+            Some(Self::Table)
+        } else if Self::STREAM_EXTENSIONS
+            .iter()
+            .any(|stream| extension.eq_ignore_ascii_case(stream))
+        {
+            Some(Self::Stream)
+        } else {
+            None
+        }
+    }
+}
+
+/// A path reaching through one or more archives, such as
+/// `archives_win32\pc0.tab\global\areasets\general.blz\general.mvdoll`.
+///
+/// Every component with an archive extension starts a new layer, so the example is read as
+/// `general.mvdoll` inside `general.blz`, which is itself inside `pc0.tab`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ArchivePath {
+    /// Path of the outermost archive.
+    pub archive: PathBuf,
+    /// Path of an entry in each layer, where all but the last are stream archives.
+    pub entries: Vec<PathBuf>,
+}
+
+impl ArchivePath {
+    /// Splits `path` into layers, accepting both `/` and `\` as separators. Returns `None` unless
+    /// the path points into at least one archive.
+    pub fn parse(path: impl AsRef<Path>) -> Option<Self> {
+        let path = path.as_ref().to_str()?;
+
+        let mut archive: Option<PathBuf> = None;
+        let mut entries = Vec::new();
+        let mut current = PathBuf::new();
+        let mut components = path.split(['/', '\\']).filter(|c| !c.is_empty()).peekable();
+        while let Some(component) = components.next() {
+            current.push(component);
+            let is_archive = ArchiveKind::from_path(Path::new(component)).is_some();
+            if is_archive && components.peek().is_some() {
+                let layer = std::mem::take(&mut current);
+                match archive {
+                    None => archive = Some(layer),
+                    Some(_) => entries.push(layer),
+                }
+            }
+        }
+        if !current.as_os_str().is_empty() {
+            entries.push(current);
+        }
+
+        Some(Self {
+            archive: archive?,
+            entries,
+        })
+        .filter(|path| !path.entries.is_empty())
+    }
+
+    /// Path of the entry in the outermost archive, which is an archive itself unless this path
+    /// only has one layer.
+    #[inline]
+    pub fn outer(&self) -> PathBuf {
+        self.archive.join(&self.entries[0])
+    }
+
+    /// Opens the file at the end of the path, with the outermost archive relative to `root`.
+    ///
+    /// Only the table of contents and entry of each layer are read, except for compressed stream
+    /// archives, which have to be inflated as a whole.
+    pub fn open(&self, root: impl AsRef<Path>) -> Result<Vec<u8>, ArchivePathError> {
+        let path = root.as_ref().join(&self.archive);
+        let entry = &self.entries[0];
+        let bytes = match ArchiveKind::from_path(&path) {
+            Some(ArchiveKind::Table) => {
+                let table = ArchiveTable::read(&mut BufReader::new(File::open(&path)?))?;
+                let found = HashString::from_path(entry)
+                    .and_then(|hash| table.entries.get(&hash))
+                    .ok_or_else(|| not_found(&path, entry))?;
+
+                let mut arc = File::open(path.with_extension("arc"))?;
+                arc.seek(SeekFrom::Start(found.offset as u64))?;
+                let mut data = vec![0u8; found.size as usize];
+                arc.read_exact(&mut data)?;
+                data
+            }
+            Some(ArchiveKind::Stream) => {
+                let mut reader = BufReader::new(File::open(&path)?);
+                let mut magic = [0u8; 1];
+                reader.read_exact(&mut magic)?;
+                reader.rewind()?;
+
+                if magic[0] == 0x78 {
+                    let mut compressed = Vec::new();
+                    reader.read_to_end(&mut compressed)?;
+                    extract(&path, compressed, entry)?
+                } else {
+                    let index = StreamArchiveIndex::read(&mut reader)?;
+                    let found = find(&index, entry).ok_or_else(|| not_found(&path, entry))?;
+                    index.extract(&mut reader, found)?
+                }
+            }
+            None => return Err(ArchivePathError::NotAnArchive { path }),
+        };
+        self.extract_inner(bytes)
+    }
+
+    /// Reads the rest of the layers, given the data of the entry at [`ArchivePath::outer`].
+    pub fn extract_inner(&self, mut bytes: Vec<u8>) -> Result<Vec<u8>, ArchivePathError> {
+        for (archive, entry) in self.entries.iter().zip(self.entries.iter().skip(1)) {
+            if ArchiveKind::from_path(archive) != Some(ArchiveKind::Stream) {
+                return Err(ArchivePathError::NotAnArchive {
+                    path: archive.clone(),
+                });
+            }
+            bytes = extract(archive, bytes, entry)?;
+        }
+        Ok(bytes)
+    }
+}
+
+/// Reads a single entry from a stream archive held in memory.
+fn extract(archive: &Path, mut bytes: Vec<u8>, entry: &Path) -> Result<Vec<u8>, ArchivePathError> {
+    if bytes.first() == Some(&0x78) {
+        let mut inflated = Vec::new();
+        flate2::read::ZlibDecoder::new(&bytes[..]).read_to_end(&mut inflated)?;
+        bytes = inflated;
+    }
+
+    let mut reader = Cursor::new(&bytes);
+    let index = StreamArchiveIndex::read(&mut reader)?;
+    let found = find(&index, entry).ok_or_else(|| not_found(archive, entry))?;
+    Ok(index.extract(&mut reader, found)?)
+}
+
+/// Stream archives name entries by file name, though some include a directory. Names are matched
+/// ignoring ASCII case, like the hashes of archive tables.
+fn find<'a>(index: &'a StreamArchiveIndex, entry: &Path) -> Option<&'a StreamArchiveIndexEntry> {
+    let full = entry.to_str()?;
+    let name = entry.file_name()?.to_str()?;
+    let find = |name: &str| {
+        index
+            .entries
+            .iter()
+            .find(|entry| entry.name.eq_ignore_ascii_case(name))
+    };
+    find(full).or_else(|| find(name))
+}
+
+#[inline]
+fn not_found(archive: &Path, entry: &Path) -> ArchivePathError {
+    ArchivePathError::NotFound {
+        archive: archive.into(),
+        entry: entry.into(),
+    }
+}
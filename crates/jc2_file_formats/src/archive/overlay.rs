@@ -0,0 +1,205 @@
+use std::{collections::HashSet, ops::Deref};
+
+use jc2_hashing::HashString;
+
+use super::{builder::entry_hash, ArchiveTable, StreamArchive, StreamArchiveIndex};
+
+/// Anything that can tell whether it holds an entry, such as an archive.
+///
+/// Entries are looked up by the hash an archive table would store for them, so stream archives
+/// match their case-folded file names. Stream archives are wrapped in [`HashedEntries`] to hash
+/// those names once.
+pub trait OverlayEntries {
+    fn contains(&self, hash: HashString) -> bool;
+}
+
+impl OverlayEntries for ArchiveTable {
+    #[inline]
+    fn contains(&self, hash: HashString) -> bool {
+        self.entries.contains_key(&hash)
+    }
+}
+
+impl OverlayEntries for HashSet<HashString> {
+    #[inline]
+    fn contains(&self, hash: HashString) -> bool {
+        HashSet::contains(self, &hash)
+    }
+}
+
+/// A stream archive along with the hashes of its entries, computed once when it is wrapped so
+/// lookups don't hash every name again.
+#[derive(Clone, Debug)]
+pub struct HashedEntries<T> {
+    value: T,
+    hashes: HashSet<HashString>,
+}
+
+impl<T> HashedEntries<T> {
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    #[inline]
+    pub fn hashes(&self) -> &HashSet<HashString> {
+        &self.hashes
+    }
+}
+
+impl<T> Deref for HashedEntries<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl From<StreamArchiveIndex> for HashedEntries<StreamArchiveIndex> {
+    #[inline]
+    fn from(value: StreamArchiveIndex) -> Self {
+        let hashes = entry_hashes(value.entries.iter().map(|entry| entry.name.as_str()));
+        Self { value, hashes }
+    }
+}
+
+impl From<StreamArchive> for HashedEntries<StreamArchive> {
+    #[inline]
+    fn from(value: StreamArchive) -> Self {
+        let hashes = entry_hashes(value.entries.iter().map(|entry| entry.name.as_str()));
+        Self { value, hashes }
+    }
+}
+
+#[inline]
+fn entry_hashes<'a>(names: impl Iterator<Item = &'a str>) -> HashSet<HashString> {
+    names.map(entry_hash).collect()
+}
+
+impl<T> OverlayEntries for HashedEntries<T> {
+    #[inline]
+    fn contains(&self, hash: HashString) -> bool {
+        self.hashes.contains(&hash)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OverlayLayer<T> {
+    pub priority: i32,
+    pub value: T,
+    sequence: u64,
+}
+
+/// Layers that shadow each other's entries, the way patch and DLC archives replace files of the
+/// base game.
+///
+/// Layers with a higher priority win. Between layers of equal priority, the one inserted first
+/// wins, so mounting without priorities keeps the order things were mounted in.
+#[derive(Clone, Debug)]
+pub struct Overlay<T> {
+    layers: Vec<OverlayLayer<T>>,
+    next_sequence: u64,
+}
+
+impl<T> Default for Overlay<T> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            layers: Vec::new(),
+            next_sequence: 0,
+        }
+    }
+}
+
+impl<T> Overlay<T> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    pub fn insert(&mut self, priority: i32, value: T) {
+        let layer = OverlayLayer {
+            priority,
+            value,
+            sequence: self.next_sequence,
+        };
+        self.next_sequence += 1;
+
+        let index = self
+            .layers
+            .partition_point(|existing| existing.priority >= priority);
+        self.layers.insert(index, layer);
+    }
+
+    #[inline]
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        self.layers.retain(|layer| f(&layer.value));
+    }
+
+    #[inline]
+    pub fn retain_mut(&mut self, mut f: impl FnMut(&mut T) -> bool) {
+        self.layers.retain_mut(|layer| f(&mut layer.value));
+    }
+
+    /// Layers from the one that wins to the one that loses.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &OverlayLayer<T>> {
+        self.layers.iter()
+    }
+
+    #[inline]
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.layers.iter().map(|layer| &layer.value)
+    }
+
+    /// Highest priority among the layers, if there are any.
+    #[inline]
+    pub fn max_priority(&self) -> Option<i32> {
+        self.layers.first().map(|layer| layer.priority)
+    }
+
+    /// Layers for which `contains` returns true, with the one that wins first.
+    #[inline]
+    pub fn candidates<'a>(
+        &'a self,
+        mut contains: impl FnMut(&T) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a OverlayLayer<T>> {
+        self.layers
+            .iter()
+            .filter(move |layer| contains(&layer.value))
+    }
+}
+
+impl<T: OverlayEntries> Overlay<T> {
+    /// The layer whose copy of an entry is used.
+    #[inline]
+    pub fn resolve(&self, hash: HashString) -> Option<&OverlayLayer<T>> {
+        self.candidates(move |value| value.contains(hash)).next()
+    }
+
+    /// Every layer holding an entry, with the one that wins first and those it shadows after.
+    #[inline]
+    pub fn resolve_all(&self, hash: HashString) -> impl Iterator<Item = &OverlayLayer<T>> {
+        self.candidates(move |value| value.contains(hash))
+    }
+}
+
+impl<T> OverlayLayer<T> {
+    /// Whether this layer wins over `other` when both hold an entry.
+    #[inline]
+    pub fn shadows(&self, other: &Self) -> bool {
+        (self.priority, std::cmp::Reverse(self.sequence))
+            > (other.priority, std::cmp::Reverse(other.sequence))
+    }
+}
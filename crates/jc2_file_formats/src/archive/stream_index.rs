@@ -0,0 +1,180 @@
+use std::{
+    io::{Read, Seek, SeekFrom},
+    ops::Deref,
+    sync::Arc,
+};
+
+use binrw::{binread, parser, BinRead, BinResult};
+
+use super::{ArchiveTableEntry, StreamArchiveEndian, StreamArchiveVersion};
+use crate::string::LengthString;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamArchiveIndexEntry {
+    pub name: String,
+    pub offset: u32,
+    pub size: u32,
+}
+
+/// Table of contents of a stream archive, parsed without reading any of the entries' data.
+#[binread]
+#[derive(Clone, Debug, Default)]
+pub struct StreamArchiveIndex {
+    pub endian: StreamArchiveEndian,
+    #[br(is_little(matches!(endian, StreamArchiveEndian::Little)))]
+    pub version: StreamArchiveVersion,
+    /// Size of the table of contents, including any padding after the last entry.
+    #[br(is_little(matches!(endian, StreamArchiveEndian::Little)))]
+    pub table_size: u32,
+    #[br(parse_with = Self::parse_entries, args(table_size))]
+    #[br(is_little(matches!(endian, StreamArchiveEndian::Little)))]
+    pub entries: Vec<StreamArchiveIndexEntry>,
+}
+
+impl StreamArchiveIndex {
+    /// Reads the index of an uncompressed archive in either endianness, leaving `reader` usable
+    /// for [`StreamArchiveIndex::extract`].
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, binrw::Error> {
+        Self::read_ne(reader)
+    }
+
+    /// Reads the index, failing if the archive was not written with `endian`.
+    pub fn read_endian<R: Read + Seek>(
+        reader: &mut R,
+        endian: StreamArchiveEndian,
+    ) -> Result<Self, binrw::Error> {
+        crate::expect_endian(reader, endian, |index: &Self| index.endian, Self::read)
+    }
+
+    #[inline]
+    pub fn find(&self, name: &str) -> Option<&StreamArchiveIndexEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// Reads the data of `entry` from the archive the index was read from.
+    pub fn extract<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        entry: &StreamArchiveIndexEntry,
+    ) -> std::io::Result<Vec<u8>> {
+        let mut data = vec![0u8; entry.size as usize];
+        reader.seek(SeekFrom::Start(entry.offset as u64))?;
+        reader.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    #[parser(reader, endian)]
+    fn parse_entries(table_size: u32) -> BinResult<Vec<StreamArchiveIndexEntry>> {
+        let table_end = reader.stream_position()? + table_size as u64;
+
+        let mut result = Vec::with_capacity(16);
+        loop {
+            // We read until the table can not contain at least one empty entry
+            let position = reader.stream_position()?;
+            let Some(table_left) = table_end.checked_sub(position) else {
+                return Err(binrw::Error::AssertFail {
+                    pos: position,
+                    message: "stream archive entry runs past the end of the table".into(),
+                });
+            };
+            if table_left < 16 {
+                break;
+            }
+
+            let name: String = LengthString::<u32>::read_options(reader, endian, ())?.into();
+            let entry = ArchiveTableEntry::read_options(reader, endian, ())?;
+
+            // Tables padded by more than one empty entry are otherwise read as nameless entries
+            if name.is_empty() {
+                break;
+            }
+            result.push(StreamArchiveIndexEntry {
+                name,
+                offset: entry.offset,
+                size: entry.size,
+            });
+        }
+
+        // Leave the reader after the table, as if the data had been read with it
+        reader.seek(SeekFrom::Start(table_end))?;
+        Ok(result)
+    }
+}
+
+/// Entry data borrowed from a [`StreamArchiveBuffer`], keeping the whole buffer alive.
+#[derive(Clone, Debug)]
+pub struct StreamArchiveSlice {
+    buffer: Arc<[u8]>,
+    offset: usize,
+    size: usize,
+}
+
+impl Deref for StreamArchiveSlice {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.buffer[self.offset..self.offset + self.size]
+    }
+}
+
+impl AsRef<[u8]> for StreamArchiveSlice {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// A whole stream archive held in one shared buffer, handing out entries without copying them.
+#[derive(Clone, Debug)]
+pub struct StreamArchiveBuffer {
+    index: StreamArchiveIndex,
+    buffer: Arc<[u8]>,
+}
+
+impl StreamArchiveBuffer {
+    /// Takes ownership of an archive's bytes, inflating them first if they are compressed.
+    pub fn new(bytes: impl Into<Arc<[u8]>>) -> Result<Self, binrw::Error> {
+        let mut buffer: Arc<[u8]> = bytes.into();
+        if buffer.first() == Some(&0x78) {
+            let mut inflated = Vec::new();
+            flate2::read::ZlibDecoder::new(&buffer[..]).read_to_end(&mut inflated)?;
+            buffer = inflated.into();
+        }
+
+        let index = StreamArchiveIndex::read(&mut std::io::Cursor::new(&buffer[..]))?;
+        for entry in &index.entries {
+            if entry.offset as usize + entry.size as usize > buffer.len() {
+                return Err(binrw::Error::Io(std::io::ErrorKind::UnexpectedEof.into()));
+            }
+        }
+        Ok(Self { index, buffer })
+    }
+
+    #[inline]
+    pub fn index(&self) -> &StreamArchiveIndex {
+        &self.index
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<StreamArchiveSlice> {
+        self.index.find(name).map(|entry| self.slice(entry))
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, StreamArchiveSlice)> {
+        self.index
+            .entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), self.slice(entry)))
+    }
+
+    #[inline]
+    fn slice(&self, entry: &StreamArchiveIndexEntry) -> StreamArchiveSlice {
+        StreamArchiveSlice {
+            buffer: self.buffer.clone(),
+            offset: entry.offset as usize,
+            size: entry.size as usize,
+        }
+    }
+}
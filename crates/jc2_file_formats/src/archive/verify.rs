@@ -0,0 +1,295 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+    io::{Read, Seek, SeekFrom},
+};
+
+use binrw::{BinRead, BinResult};
+use jc2_hashing::{HashList, HashString};
+use thiserror::Error;
+
+use super::{
+    ArchiveEndian, ArchiveTable, ArchiveTableEntry, StreamArchive, StreamArchiveIndex,
+    ARCHIVE_ALIGNMENT,
+};
+
+/// A structural problem in an archive, naming the entry it was found in.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum ArchiveIssue<K> {
+    #[error("{key:?} ends at {end:#x}, past the end of the archive at {archive_size:#x}")]
+    PastEnd { key: K, end: u64, archive_size: u64 },
+    #[error("{key:?} overlaps {other:?}")]
+    Overlapping { key: K, other: K },
+    #[error("{key:?} starts inside the table of contents at {offset:#x}")]
+    InsideTable { key: K, offset: u64 },
+    #[error("{key:?} starts at {offset:#x}, which is not aligned to {alignment:#x}")]
+    Misaligned { key: K, offset: u64, alignment: u64 },
+    #[error("{key:?} is empty")]
+    Empty { key: K },
+    #[error("{key:?} appears more than once")]
+    Duplicate { key: K },
+}
+
+/// Checks the ranges of a set of entries, reporting issues in offset order. Entries sharing the
+/// exact same range, as written when deduplicating, are not overlapping.
+fn verify_ranges<K: Clone + Debug>(
+    mut ranges: Vec<(K, u64, u64)>,
+    archive_size: u64,
+    alignment: Option<u64>,
+) -> Vec<ArchiveIssue<K>> {
+    ranges.sort_by_key(|(_, offset, size)| (*offset, *size));
+
+    let mut issues = Vec::new();
+    let mut furthest: Option<(K, u64, u64)> = None;
+    for (key, offset, size) in ranges {
+        let end = offset + size;
+        if let Some(alignment) = alignment.filter(|alignment| offset % alignment != 0) {
+            issues.push(ArchiveIssue::Misaligned {
+                key: key.clone(),
+                offset,
+                alignment,
+            });
+        }
+        if size == 0 {
+            issues.push(ArchiveIssue::Empty { key });
+            continue;
+        }
+        if end > archive_size {
+            issues.push(ArchiveIssue::PastEnd {
+                key: key.clone(),
+                end,
+                archive_size,
+            });
+        }
+        match &furthest {
+            Some((_, other_offset, other_end)) if (offset, end) == (*other_offset, *other_end) => {}
+            Some((other, _, other_end)) if offset < *other_end => {
+                issues.push(ArchiveIssue::Overlapping {
+                    key: key.clone(),
+                    other: other.clone(),
+                });
+                if end > *other_end {
+                    furthest = Some((key, offset, end));
+                }
+            }
+            _ => furthest = Some((key, offset, end)),
+        }
+    }
+    issues
+}
+
+impl ArchiveTable {
+    /// Checks the table against the size of the `.arc` it describes.
+    pub fn verify(&self, archive_size: u64) -> Vec<ArchiveIssue<HashString>> {
+        let ranges = self
+            .entries
+            .iter()
+            .map(|(hash, entry)| (*hash, entry.offset as u64, entry.size as u64))
+            .collect();
+        verify_ranges(ranges, archive_size, Some(ARCHIVE_ALIGNMENT as u64))
+    }
+
+    /// Reports hashes listed more than once in the table held by `reader`, which can't be found
+    /// by [`ArchiveTable::verify`] as reading a table keeps only the last of them.
+    pub fn find_duplicates<R: Read + Seek>(
+        reader: &mut R,
+    ) -> BinResult<Vec<ArchiveIssue<HashString>>> {
+        let endian = ArchiveEndian::read_ne(reader)?.into();
+        let stream_position = reader.stream_position()?;
+        let stream_length = reader.seek(SeekFrom::End(0))?;
+        let count = (stream_length - stream_position) / 12;
+        reader.seek(SeekFrom::Start(stream_position))?;
+
+        let mut hashes = Vec::new();
+        for _ in 0..count {
+            hashes.push(HashString::read_options(reader, endian, ())?);
+            ArchiveTableEntry::read_options(reader, endian, ())?;
+        }
+        Ok(duplicates(hashes.into_iter()))
+    }
+}
+
+impl StreamArchiveIndex {
+    /// Checks the index against the size of the archive it was read from.
+    pub fn verify(&self, archive_size: u64) -> Vec<ArchiveIssue<String>> {
+        // Magic, version and the table size come before the table itself
+        let table_end = 16 + self.table_size as u64;
+
+        let mut issues = duplicates(self.entries.iter().map(|entry| entry.name.clone()));
+        issues.extend(
+            self.entries
+                .iter()
+                .filter(|entry| (entry.offset as u64) < table_end)
+                .map(|entry| ArchiveIssue::InsideTable {
+                    key: entry.name.clone(),
+                    offset: entry.offset as u64,
+                }),
+        );
+
+        let ranges = self
+            .entries
+            .iter()
+            .map(|entry| (entry.name.clone(), entry.offset as u64, entry.size as u64))
+            .collect();
+        issues.extend(verify_ranges(ranges, archive_size, None));
+        issues
+    }
+}
+
+impl StreamArchive {
+    /// Checks for entries the game could not tell apart or would load as nothing.
+    ///
+    /// Offsets are laid out again when writing, so only an index read from a file can have
+    /// problems with its ranges, see [`StreamArchiveIndex::verify`].
+    pub fn verify(&self) -> Vec<ArchiveIssue<String>> {
+        let mut issues = duplicates(self.entries.iter().map(|entry| entry.name.clone()));
+        issues.extend(
+            self.entries
+                .iter()
+                .filter(|entry| entry.data.is_empty())
+                .map(|entry| ArchiveIssue::Empty {
+                    key: entry.name.clone(),
+                }),
+        );
+        issues
+    }
+}
+
+fn duplicates<K: Clone + Eq + Hash>(keys: impl Iterator<Item = K>) -> Vec<ArchiveIssue<K>> {
+    let mut seen = HashSet::new();
+    let mut reported = HashSet::new();
+    keys.filter(|key| !seen.insert(key.clone()) && reported.insert(key.clone()))
+        .map(|key| ArchiveIssue::Duplicate { key })
+        .collect()
+}
+
+/// A hash from an archive table, with the name it was hashed from if known.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NamedHash {
+    pub hash: HashString,
+    pub name: Option<String>,
+}
+
+/// Entries that differ between two builds of an archive, each list sorted by key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveDiff<K> {
+    pub added: Vec<K>,
+    pub removed: Vec<K>,
+    /// Entries present in both whose contents differ.
+    pub changed: Vec<K>,
+}
+
+impl<K> Default for ArchiveDiff<K> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+}
+
+impl<K> ArchiveDiff<K> {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Compares two sets of entries, calling `changed` for keys present in both.
+    fn compare<V, E>(
+        old: &HashMap<K, V>,
+        new: &HashMap<K, V>,
+        mut changed: impl FnMut(&V, &V) -> Result<bool, E>,
+    ) -> Result<Self, E>
+    where
+        K: Clone + Ord + Hash,
+    {
+        let mut result = Self::default();
+        for (key, old_value) in old {
+            match new.get(key) {
+                Some(new_value) => {
+                    if changed(old_value, new_value)? {
+                        result.changed.push(key.clone());
+                    }
+                }
+                None => result.removed.push(key.clone()),
+            }
+        }
+        result
+            .added
+            .extend(new.keys().filter(|key| !old.contains_key(*key)).cloned());
+
+        result.added.sort_unstable();
+        result.removed.sort_unstable();
+        result.changed.sort_unstable();
+        Ok(result)
+    }
+}
+
+impl ArchiveDiff<HashString> {
+    /// Compares two `.tab`s, reading entries of matching size from their `.arc`s to find changes.
+    pub fn tables<A: Read + Seek, B: Read + Seek>(
+        old: &ArchiveTable,
+        old_archive: &mut A,
+        new: &ArchiveTable,
+        new_archive: &mut B,
+    ) -> std::io::Result<Self> {
+        Self::compare(&old.entries, &new.entries, |old_entry, new_entry| {
+            if old_entry.size != new_entry.size {
+                return Ok(true);
+            }
+            let mut old_data = vec![0u8; old_entry.size as usize];
+            old_archive.seek(SeekFrom::Start(old_entry.offset as u64))?;
+            old_archive.read_exact(&mut old_data)?;
+
+            let mut new_data = vec![0u8; new_entry.size as usize];
+            new_archive.seek(SeekFrom::Start(new_entry.offset as u64))?;
+            new_archive.read_exact(&mut new_data)?;
+            Ok(old_data != new_data)
+        })
+    }
+
+    /// Looks up the name of every entry in `hashes`.
+    pub fn with_names(self, hashes: &HashList) -> ArchiveDiff<NamedHash> {
+        let name = |hash: HashString| NamedHash {
+            hash,
+            name: hashes.find(hash).and_then(|entry| {
+                entry
+                    .as_string()
+                    .cloned()
+                    .or_else(|| Some(entry.as_path()?.to_string_lossy().into_owned()))
+            }),
+        };
+        ArchiveDiff {
+            added: self.added.into_iter().map(name).collect(),
+            removed: self.removed.into_iter().map(name).collect(),
+            changed: self.changed.into_iter().map(name).collect(),
+        }
+    }
+}
+
+impl ArchiveDiff<String> {
+    /// Compares two stream archives by entry name.
+    pub fn stream_archives(old: &StreamArchive, new: &StreamArchive) -> Self {
+        fn entries(archive: &StreamArchive) -> HashMap<&str, &[u8]> {
+            archive
+                .entries
+                .iter()
+                .map(|entry| (entry.name.as_str(), entry.data.as_slice()))
+                .collect()
+        }
+        let diff = ArchiveDiff::compare(&entries(old), &entries(new), |old, new| {
+            Ok::<_, std::convert::Infallible>(old != new)
+        })
+        .unwrap_or_else(|never| match never {});
+
+        let owned = |names: Vec<&str>| names.into_iter().map(str::to_owned).collect();
+        Self {
+            added: owned(diff.added),
+            removed: owned(diff.removed),
+            changed: owned(diff.changed),
+        }
+    }
+}
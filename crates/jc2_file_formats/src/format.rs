@@ -0,0 +1,216 @@
+use std::{any::Any, collections::HashMap, io::Cursor};
+
+use thiserror::Error;
+
+use crate::{
+    archive::{ArchiveTable, StreamArchive},
+    render_block_model::RenderBlockModel,
+};
+
+/// Kind of file, as told by its contents rather than its name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FileFormat {
+    /// `.rbm`
+    RenderBlockModel,
+    /// Uncompressed `.bl`, `.ee`, `.fl` or `.nl`
+    StreamArchive,
+    /// `.tab`
+    ArchiveTable,
+    /// Zlib stream, such as a compressed stream archive
+    Zlib,
+    /// `.dds`
+    DirectDrawSurface,
+    /// `.bik`
+    Bink,
+    /// `.wav` and other RIFF containers
+    Riff,
+    /// `.fsb`
+    FmodSoundBank,
+    /// Compiled `.luac`
+    LuaBytecode,
+    /// `.xml`
+    Xml,
+    Unknown,
+}
+
+impl FileFormat {
+    /// Usual extension of files in this format, without the leading dot.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Self::RenderBlockModel => Some("rbm"),
+            Self::StreamArchive => Some("ee"),
+            Self::ArchiveTable => Some("tab"),
+            Self::Zlib => Some("z"),
+            Self::DirectDrawSurface => Some("dds"),
+            Self::Bink => Some("bik"),
+            Self::Riff => Some("wav"),
+            Self::FmodSoundBank => Some("fsb"),
+            Self::LuaBytecode => Some("luac"),
+            Self::Xml => Some("xml"),
+            Self::Unknown => None,
+        }
+    }
+}
+
+/// Recognizes a file from its magic.
+pub fn detect_format(bytes: &[u8]) -> FileFormat {
+    let magic =
+        |offset: usize, magic: &[u8]| bytes.get(offset..offset + magic.len()) == Some(magic);
+
+    if (magic(0, b"\x05\x00\x00\x00") || magic(0, b"\x00\x00\x00\x05")) && magic(4, b"RBMDL") {
+        FileFormat::RenderBlockModel
+    } else if (magic(0, b"\x04\x00\x00\x00") || magic(0, b"\x00\x00\x00\x04")) && magic(4, b"SARC")
+    {
+        FileFormat::StreamArchive
+    } else if magic(0, b"DDS ") {
+        FileFormat::DirectDrawSurface
+    } else if magic(0, b"BIK") {
+        FileFormat::Bink
+    } else if magic(0, b"RIFF") {
+        FileFormat::Riff
+    } else if magic(0, b"FSB") {
+        FileFormat::FmodSoundBank
+    } else if magic(0, b"\x1bLua") {
+        FileFormat::LuaBytecode
+    } else if magic(0, b"<?xml") || magic(0, b"\xef\xbb\xbf<?xml") {
+        FileFormat::Xml
+    } else if is_zlib(bytes) {
+        FileFormat::Zlib
+    } else if (magic(0, b"\x00\x08\x00\x00") || magic(0, b"\x00\x00\x08\x00"))
+        && (bytes.len() - 4) % 12 == 0
+    {
+        // The table header is short, so also check that the rest is made of whole entries
+        FileFormat::ArchiveTable
+    } else {
+        FileFormat::Unknown
+    }
+}
+
+#[inline]
+fn is_zlib(bytes: &[u8]) -> bool {
+    match bytes {
+        [cmf, flg, ..] => *cmf == 0x78 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0,
+        _ => false,
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum FormatError {
+    #[error("invalid file: {0}")]
+    Binrw(#[from] binrw::Error),
+    #[error("no parser registered for {0:?}")]
+    Unsupported(FileFormat),
+}
+
+/// A file parsed by a [`FormatRegistry`], holding whichever type was registered for its format.
+#[derive(Debug)]
+pub struct ParsedFile {
+    pub format: FileFormat,
+    pub value: Box<dyn Any + Send + Sync>,
+}
+
+impl ParsedFile {
+    #[inline]
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.value.downcast_ref()
+    }
+
+    #[inline]
+    pub fn downcast<T: Any>(self) -> Result<T, Self> {
+        match self.value.downcast() {
+            Ok(value) => Ok(*value),
+            Err(value) => Err(Self {
+                format: self.format,
+                value,
+            }),
+        }
+    }
+}
+
+type FormatParser = Box<dyn Fn(&[u8]) -> Result<ParsedFile, FormatError> + Send + Sync>;
+
+/// Parsers to open files by their detected format.
+///
+/// The default registry opens models, stream archives and archive tables. Zlib streams are
+/// inflated and parsed as whatever they contain.
+pub struct FormatRegistry {
+    parsers: HashMap<FileFormat, FormatParser>,
+}
+
+impl FormatRegistry {
+    /// A registry without any parsers.
+    #[inline]
+    pub fn empty() -> Self {
+        Self {
+            parsers: HashMap::new(),
+        }
+    }
+
+    /// Registers `parser` for `format`, replacing any previous one.
+    pub fn register<T, F>(&mut self, format: FileFormat, parser: F) -> &mut Self
+    where
+        T: Any + Send + Sync,
+        F: Fn(&[u8]) -> Result<T, FormatError> + Send + Sync + 'static,
+    {
+        self.parsers.insert(
+            format,
+            Box::new(move |bytes| {
+                Ok(ParsedFile {
+                    format,
+                    value: Box::new(parser(bytes)?),
+                })
+            }),
+        );
+        self
+    }
+
+    #[inline]
+    pub fn supports(&self, format: FileFormat) -> bool {
+        self.parsers.contains_key(&format)
+    }
+
+    /// Detects the format of `bytes` and parses them with the registered parser.
+    pub fn parse(&self, bytes: &[u8]) -> Result<ParsedFile, FormatError> {
+        let format = detect_format(bytes);
+        if format == FileFormat::Zlib {
+            // Compressed files are only useful once inflated
+            let mut inflated = Vec::new();
+            std::io::Read::read_to_end(&mut flate2::read::ZlibDecoder::new(bytes), &mut inflated)
+                .map_err(binrw::Error::Io)?;
+            if detect_format(&inflated) != FileFormat::Zlib {
+                return self.parse(&inflated);
+            }
+        }
+
+        let parser = self
+            .parsers
+            .get(&format)
+            .ok_or(FormatError::Unsupported(format))?;
+        parser(bytes)
+    }
+}
+
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        let mut result = Self::empty();
+        result
+            .register(FileFormat::RenderBlockModel, |bytes| {
+                Ok(RenderBlockModel::read(&mut Cursor::new(bytes))?)
+            })
+            .register(FileFormat::StreamArchive, |bytes| {
+                Ok(StreamArchive::read(&mut Cursor::new(bytes))?)
+            })
+            .register(FileFormat::ArchiveTable, |bytes| {
+                Ok(ArchiveTable::read(&mut Cursor::new(bytes))?)
+            });
+        result
+    }
+}
+
+impl std::fmt::Debug for FormatRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FormatRegistry")
+            .field("formats", &self.parsers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
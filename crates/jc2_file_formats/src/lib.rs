@@ -1,4 +1,43 @@
 pub mod archive;
+pub mod format;
 pub mod math;
 pub mod render_block_model;
 pub mod string;
+
+use std::{
+    fmt::Debug,
+    io::{Seek, SeekFrom},
+};
+
+use binrw::BinResult;
+
+/// Runs `read` and seeks back to where the reader started, whether or not it succeeded.
+fn peek<R: Seek, T>(reader: &mut R, read: impl FnOnce(&mut R) -> BinResult<T>) -> BinResult<T> {
+    let position = reader.stream_position()?;
+    let result = read(reader);
+    reader.seek(SeekFrom::Start(position))?;
+    result
+}
+
+/// Runs `read` and fails with [`binrw::Error::BadMagic`] if the file's magic marks it as written
+/// with any endianness other than `expected`.
+fn expect_endian<R: Seek, T, E>(
+    reader: &mut R,
+    expected: E,
+    endian: impl FnOnce(&T) -> E,
+    read: impl FnOnce(&mut R) -> BinResult<T>,
+) -> BinResult<T>
+where
+    E: PartialEq + Debug + Send + Sync + 'static,
+{
+    let pos = reader.stream_position()?;
+    let result = read(reader)?;
+    let found = endian(&result);
+    if found != expected {
+        return Err(binrw::Error::BadMagic {
+            pos,
+            found: Box::new(found),
+        });
+    }
+    Ok(result)
+}
@@ -10,6 +10,7 @@ use super::{
 #[binrw]
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vec3<T: VecType> {
     pub x: T,
     pub y: T,
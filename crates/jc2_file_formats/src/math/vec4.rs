@@ -8,6 +8,7 @@ use super::{
 #[binrw]
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vec4<T: VecType> {
     pub x: T,
     pub y: T,
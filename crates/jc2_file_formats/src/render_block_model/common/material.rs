@@ -5,6 +5,7 @@ use crate::string::LengthString;
 #[binrw]
 #[brw(repr = u32)]
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PrimitiveType {
     #[default]
     TriangleList,
@@ -20,6 +21,7 @@ pub enum PrimitiveType {
 
 #[binrw]
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Material {
     pub textures: [LengthString<u32>; Material::MAX_TEXTURE_COUNT],
     pub primitive_type: PrimitiveType,
@@ -53,8 +53,8 @@ impl From<Vec2<f32>> for PackedUVI16 {
     #[inline]
     fn from(value: Vec2<f32>) -> Self {
         Self(
-            (value.x.clamp(-1.0, 1.0) * i16::MAX as f32) as i16,
-            (value.y.clamp(-1.0, 1.0) * i16::MAX as f32) as i16,
+            (value.x.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16,
+            (value.y.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16,
         )
     }
 }
@@ -77,9 +77,9 @@ impl From<Vec3<f32>> for PackedPosition {
     #[inline]
     fn from(value: Vec3<f32>) -> Self {
         Self(
-            (value.x.clamp(-1.0, 1.0) * i16::MAX as f32) as i16,
-            (value.y.clamp(-1.0, 1.0) * i16::MAX as f32) as i16,
-            (value.z.clamp(-1.0, 1.0) * i16::MAX as f32) as i16,
+            (value.x.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16,
+            (value.y.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16,
+            (value.z.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16,
         )
     }
 }
@@ -95,6 +95,12 @@ impl From<PackedPosition> for Vec3<f32> {
     }
 }
 
+/// Maps -1..1 to a byte stored in a float, where decoding gives `byte / 128 - 1`.
+#[inline]
+fn packed_byte(value: f32) -> f32 {
+    ((value + 1.0) * 128.0).floor().clamp(0.0, 255.0)
+}
+
 #[binrw]
 #[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
 pub struct PackedNormalF32(f32);
@@ -103,9 +109,9 @@ impl From<Vec3<f32>> for PackedNormalF32 {
     #[inline]
     fn from(value: Vec3<f32>) -> Self {
         Self({
-            let x = ((value.x + 1.0) * 127.0).floor() / 256.0;
-            let y = ((value.y + 1.0) * 127.0).floor();
-            let z = ((value.z + 1.0) * 127.0).floor() * 256.0;
+            let x = packed_byte(value.x) / 256.0;
+            let y = packed_byte(value.y);
+            let z = packed_byte(value.z) * 256.0;
             x + y + z
         })
     }
@@ -131,9 +137,9 @@ impl From<Vec4<f32>> for PackedTangentF32 {
     #[inline]
     fn from(value: Vec4<f32>) -> Self {
         Self({
-            let x = ((value.x + 1.0) * 127.0).floor() / 256.0;
-            let y = ((value.y + 1.0) * 127.0).floor();
-            let z = ((value.z + 1.0) * 127.0).floor() * 256.0;
+            let x = packed_byte(value.x) / 256.0;
+            let y = packed_byte(value.y);
+            let z = packed_byte(value.z) * 256.0;
             (x + y + z).copysign(value.w)
         })
     }
@@ -161,9 +167,9 @@ impl From<Vec3<f32>> for PackedNormalU32 {
     #[inline]
     fn from(value: Vec3<f32>) -> Self {
         Self({
-            let x = ((((value.x * 127.0) + 128.0) as u32) & 0xFF) << 24;
-            let y = ((((value.y * 127.0) + 128.0) as u32) & 0xFF) << 16;
-            let z = ((((value.z * 127.0) + 128.0) as u32) & 0xFF) << 8;
+            let x = ((((value.x * 127.0) + 128.0).round() as u32) & 0xFF) << 24;
+            let y = ((((value.y * 127.0) + 128.0).round() as u32) & 0xFF) << 16;
+            let z = ((((value.z * 127.0) + 128.0).round() as u32) & 0xFF) << 8;
             x + y + z + 128u32
         })
     }
@@ -214,12 +220,14 @@ pub struct PackedRGBAF32(f32);
 impl From<Vec4<f32>> for PackedRGBAF32 {
     #[inline]
     fn from(value: Vec4<f32>) -> Self {
+        // Six bits for each of y, z and w, with x taking the fraction, so that decoding sees each
+        // channel in the fraction of the value scaled by its place
         Self({
             let x = (value.x * 1.0).fract();
-            let y = (value.y * 64.0).floor();
-            let z = (value.z * 4096.0).floor();
-            let w = (value.w * 262144.0).floor();
-            x + y + z + w
+            let y = (value.y * 64.0).floor().clamp(0.0, 63.0);
+            let z = (value.z * 64.0).floor().clamp(0.0, 63.0) * 64.0;
+            let w = (value.w * 64.0).floor().clamp(0.0, 63.0) * 4096.0;
+            w + z + y + x
         })
     }
 }
@@ -243,13 +251,14 @@ pub struct PackedVec4F32(u32);
 impl From<Vec4<f32>> for PackedVec4F32 {
     #[inline]
     fn from(value: Vec4<f32>) -> Self {
-        Self({
-            let x = (value.x * 255.0) as u32;
-            let y = (value.y * 255.0) as u32;
-            let z = (value.z * 255.0) as u32;
-            let w = (value.z * 255.0) as u32;
-            x | (y << 4) | (z << 8) | (w << 12)
-        })
+        // A byte for each channel, x in the lowest
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u32;
+        Self(
+            channel(value.x)
+                | (channel(value.y) << 8)
+                | (channel(value.z) << 16)
+                | (channel(value.w) << 24),
+        )
     }
 }
 
@@ -258,9 +267,9 @@ impl From<PackedVec4F32> for Vec4<f32> {
     fn from(value: PackedVec4F32) -> Self {
         Self {
             x: (value.0 & 0xFF) as f32 / 255.0,
-            y: ((value.0 >> 4) & 0xFF) as f32 / 255.0,
-            z: ((value.0 >> 8) & 0xFF) as f32 / 255.0,
-            w: ((value.0 >> 12) & 0xFF) as f32 / 255.0,
+            y: ((value.0 >> 8) & 0xFF) as f32 / 255.0,
+            z: ((value.0 >> 16) & 0xFF) as f32 / 255.0,
+            w: ((value.0 >> 24) & 0xFF) as f32 / 255.0,
         }
     }
 }
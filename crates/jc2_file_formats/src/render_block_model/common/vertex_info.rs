@@ -1,10 +1,14 @@
 use binrw::{binrw, BinRead, BinWrite};
 
-use crate::math::{Vec2, Vec4};
+use crate::{
+    math::{Vec2, Vec4},
+    render_block_model::GenericVertex,
+};
 
 #[binrw]
 #[brw(repr = u32)]
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VertexFormat {
     #[default]
     F32,
@@ -12,6 +16,7 @@ pub enum VertexFormat {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VertexInfo {
     pub format: VertexFormat,
     pub scale: f32,
@@ -34,6 +39,68 @@ impl Default for VertexInfo {
     }
 }
 
+impl VertexInfo {
+    /// Largest error allowed when packing positions as [`VertexFormat::I16`], in model units.
+    pub const MAX_POSITION_ERROR: f32 = 1.0 / 1024.0;
+    /// Largest error allowed when packing texture coordinates as [`VertexFormat::I16`].
+    pub const MAX_UV_ERROR: f32 = 1.0 / 4096.0;
+
+    /// Picks the format, scale and extents that fit `vertices`, packing them as
+    /// [`VertexFormat::I16`] unless that would lose too much precision.
+    pub fn fit(vertices: &[GenericVertex]) -> Self {
+        let scale = position_extent(vertices);
+        let uv0_extent = uv_extent(vertices.iter().map(|vertex| vertex.uv0));
+        let uv1_extent = uv_extent(vertices.iter().map(|vertex| vertex.uv1));
+
+        let packable = fits_i16(scale, Self::MAX_POSITION_ERROR)
+            && [uv0_extent, uv1_extent]
+                .iter()
+                .all(|extent| fits_i16(extent.x.max(extent.y), Self::MAX_UV_ERROR));
+        if packable {
+            Self {
+                format: VertexFormat::I16,
+                scale,
+                uv0_extent,
+                uv1_extent,
+                ..Default::default()
+            }
+        } else {
+            Self::default()
+        }
+    }
+}
+
+/// Largest distance from the origin along any axis, or 1 if there is none.
+pub(crate) fn position_extent(vertices: &[GenericVertex]) -> f32 {
+    let extent = vertices
+        .iter()
+        .map(|vertex| {
+            let position = vertex.position;
+            position.x.abs().max(position.y.abs()).max(position.z.abs())
+        })
+        .fold(0.0, f32::max);
+    if extent > 0.0 {
+        extent
+    } else {
+        1.0
+    }
+}
+
+fn uv_extent(uvs: impl Iterator<Item = Vec2<f32>>) -> Vec2<f32> {
+    let extent = uvs.fold(Vec2::splat(0.0f32), |extent, uv| {
+        Vec2::new(extent.x.max(uv.x.abs()), extent.y.max(uv.y.abs()))
+    });
+    Vec2::new(
+        if extent.x > 0.0 { extent.x } else { 1.0 },
+        if extent.y > 0.0 { extent.y } else { 1.0 },
+    )
+}
+
+/// Whether values up to `extent` are within `max_error` once stored as 16 bit integers.
+pub(crate) fn fits_i16(extent: f32, max_error: f32) -> bool {
+    extent.is_finite() && extent / i16::MAX as f32 * 0.5 <= max_error
+}
+
 impl BinRead for VertexInfo {
     type Args<'a> = (bool,);
 
@@ -74,7 +141,7 @@ impl BinWrite for VertexInfo {
         self.format.write_options(writer, endian, ())?;
         self.scale.write_options(writer, endian, ())?;
         if args.0 {
-            self.uv1_extent.write_options(writer, endian, ())?;
+            self.uv0_extent.write_options(writer, endian, ())?;
             self.uv1_extent.write_options(writer, endian, ())?;
         } else {
             self.uv0_extent.x.write_options(writer, endian, ())?;
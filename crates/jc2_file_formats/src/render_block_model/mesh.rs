@@ -0,0 +1,574 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use bitflags::bitflags;
+
+use crate::math::{
+    ops::{VecCross, VecDot},
+    Vec2, Vec3, Vec4,
+};
+
+use super::{
+    fits_i16, position_extent, BillboardFoliageRenderBlock, CarPaintRenderBlock,
+    CarPaintSimpleRenderBlock, DeformableWindowRenderBlock, FacadeAttributes, FacadeRenderBlock,
+    GeneralAttributes, GeneralRenderBlock, GenericVertex, HaloRenderBlock, IndexBuffer,
+    LambertAttributes, LambertRenderBlock, Material, PrimitiveType, RenderBlock, RenderBlockError,
+    SkinBatch, SkinnedGeneralAttributes, SkinnedGeneralFlags, SkinnedGeneralRenderBlock,
+    VegetationBarkRenderBlock, VegetationFoliageRenderBlock, Vertex, VertexBuffer, VertexFormat,
+    VertexInfo, WindowRenderBlock,
+};
+
+bitflags! {
+    /// Parts of a [`GenericVertex`] that a block stores, the others being left at their defaults.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct MeshAttributes: u32 {
+        const POSITION = 1 << 0;
+        const NORMAL = 1 << 1;
+        const TANGENT = 1 << 2;
+        const BINORMAL = 1 << 3;
+        const UV0 = 1 << 4;
+        const UV1 = 1 << 5;
+        const UV2 = 1 << 6;
+        const COLOR = 1 << 7;
+        const SIZE = 1 << 8;
+        const SKIN = 1 << 9;
+        const MORPH = 1 << 10;
+    }
+}
+
+/// The geometry of any [`RenderBlock`], with vertices converted to [`GenericVertex`].
+///
+/// Positions and texture coordinates are in model space, with the scale and extents of the block
+/// already applied. Billboards and halos keep their dimensions in `uv1`, and lit deformable
+/// vertices their light in `size`. Bone indices of skinned blocks are resolved through their skin
+/// batches to bones of the skeleton.
+#[derive(Clone, Debug, Default)]
+pub struct GenericMesh<'a> {
+    pub vertices: Vec<GenericVertex>,
+    pub attributes: MeshAttributes,
+    /// Number of bone weights and indices used by each vertex of a skinned mesh.
+    pub bone_influences: usize,
+    pub indices: &'a [u16],
+    pub primitive_type: PrimitiveType,
+    pub textures: [&'a str; Material::MAX_TEXTURE_COUNT],
+}
+
+impl<'a> GenericMesh<'a> {
+    fn new<T: Clone + Into<GenericVertex>>(
+        vertices: &[T],
+        indices: &'a [u16],
+        material: Option<&'a Material>,
+        attributes: MeshAttributes,
+    ) -> Self {
+        let mut result = Self {
+            vertices: vertices.iter().cloned().map(Into::into).collect(),
+            attributes,
+            indices,
+            ..Default::default()
+        };
+        if let Some(material) = material {
+            result.primitive_type = material.primitive_type;
+            for (texture, name) in result.textures.iter_mut().zip(&material.textures) {
+                *texture = name.as_ref();
+            }
+        }
+        result
+    }
+
+    #[inline]
+    fn with_bone_influences(mut self, bone_influences: usize) -> Self {
+        self.bone_influences = bone_influences;
+        self
+    }
+
+    /// Resolves bone indices, which index the palette of the skin batch drawing the vertex, to
+    /// bones of the skeleton. Vertices drawn by several batches are resolved by the first, and
+    /// those drawn by none keep their indices.
+    fn with_skin_batches(mut self, batches: &[SkinBatch]) -> Self {
+        let influences = self.bone_influences;
+        let mut resolved = vec![false; self.vertices.len()];
+        for batch in batches {
+            let start = batch.offset as usize;
+            let end = start
+                .saturating_add(batch.size as usize)
+                .min(self.indices.len());
+            let Some(indices) = self.indices.get(start..end) else {
+                continue;
+            };
+            for &index in indices {
+                let index = usize::from(index);
+                if resolved.get(index) != Some(&false) {
+                    continue;
+                }
+                resolved[index] = true;
+                for bone in &mut self.vertices[index].bone_indices[..influences] {
+                    if let Some(&palette_bone) = batch.bone_indices.get(*bone as usize) {
+                        *bone = u32::from(palette_bone);
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    fn with_scale(mut self, scale: f32, uv0_extent: Vec2<f32>, uv1_extent: Vec2<f32>) -> Self {
+        for vertex in &mut self.vertices {
+            vertex.position = vertex.position * scale;
+            vertex.uv0 = Vec2::new(vertex.uv0.x * uv0_extent.x, vertex.uv0.y * uv0_extent.y);
+            vertex.uv1 = Vec2::new(vertex.uv1.x * uv1_extent.x, vertex.uv1.y * uv1_extent.y);
+        }
+        self
+    }
+
+    #[inline]
+    fn with_vertex_info(self, vertex_info: &VertexInfo) -> Self {
+        self.with_scale(
+            vertex_info.scale,
+            vertex_info.uv0_extent,
+            vertex_info.uv1_extent,
+        )
+    }
+
+    /// Collects `f` of every vertex, if the mesh has `attribute`.
+    #[inline]
+    pub fn attribute<T>(
+        &self,
+        attribute: MeshAttributes,
+        f: impl FnMut(&GenericVertex) -> T,
+    ) -> Option<Vec<T>> {
+        self.attributes
+            .contains(attribute)
+            .then(|| self.vertices.iter().map(f).collect())
+    }
+
+    #[inline]
+    pub fn positions(&self) -> Vec<Vec3<f32>> {
+        self.vertices.iter().map(|vertex| vertex.position).collect()
+    }
+
+    #[inline]
+    pub fn normals(&self) -> Option<Vec<Vec3<f32>>> {
+        self.attribute(MeshAttributes::NORMAL, |vertex| vertex.normal)
+    }
+
+    /// Tangents with the handedness of the binormal in `w`.
+    #[inline]
+    pub fn tangents(&self) -> Option<Vec<Vec4<f32>>> {
+        self.attribute(MeshAttributes::TANGENT, |vertex| {
+            let sign = vertex
+                .normal
+                .cross(vertex.tangent)
+                .dot(vertex.binormal)
+                .signum();
+            vertex.tangent.extend(sign)
+        })
+    }
+
+    #[inline]
+    pub fn uvs(&self, channel: usize) -> Option<Vec<Vec2<f32>>> {
+        match channel {
+            0 => self.attribute(MeshAttributes::UV0, |vertex| vertex.uv0),
+            1 => self.attribute(MeshAttributes::UV1, |vertex| vertex.uv1),
+            2 => self.attribute(MeshAttributes::UV2, |vertex| vertex.uv2),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn colors(&self) -> Option<Vec<Vec4<f32>>> {
+        self.attribute(MeshAttributes::COLOR, |vertex| vertex.diffuse_color)
+    }
+
+    #[inline]
+    pub fn bone_weights(&self) -> Option<Vec<[f32; 8]>> {
+        self.attribute(MeshAttributes::SKIN, |vertex| vertex.bone_weights)
+    }
+
+    #[inline]
+    pub fn bone_indices(&self) -> Option<Vec<[u32; 8]>> {
+        self.attribute(MeshAttributes::SKIN, |vertex| vertex.bone_indices)
+    }
+
+    /// Positions, normals and tangents of the morph target.
+    #[inline]
+    pub fn morph_target(&self) -> Option<Vec<[Vec3<f32>; 3]>> {
+        self.attribute(MeshAttributes::MORPH, |vertex| {
+            [
+                vertex.morph_position,
+                vertex.morph_normal,
+                vertex.morph_tangent,
+            ]
+        })
+    }
+}
+
+impl RenderBlock {
+    /// The material of the block, if it has one.
+    pub fn material(&self) -> Option<&Material> {
+        match self {
+            Self::BillboardFoliage(block) => Some(&block.material),
+            Self::CarPaint(block) => Some(&block.material),
+            Self::CarPaintSimple(block) => Some(&block.material),
+            Self::DeformableWindow(block) => Some(&block.material),
+            Self::Facade(block) => Some(&block.material),
+            Self::General(block) => Some(&block.material),
+            Self::Halo(block) => Some(&block.material),
+            Self::Lambert(block) => Some(&block.material),
+            Self::SkinnedGeneral(block) => Some(&block.material),
+            Self::VegetationBark(block) => Some(&block.material),
+            Self::VegetationFoliage(block) => Some(&block.material),
+            Self::Window(block) => Some(&block.material),
+            Self::Unknown { .. } => None,
+        }
+    }
+
+    /// Views the geometry of the block the same way whatever its type, or `None` for blocks that
+    /// couldn't be parsed.
+    pub fn as_mesh(&self) -> Option<GenericMesh<'_>> {
+        type A = MeshAttributes;
+        let material = self.material();
+        let lit = A::POSITION | A::NORMAL | A::TANGENT | A::BINORMAL;
+        let general = lit | A::UV0 | A::UV1 | A::COLOR;
+        let deformable = A::POSITION | A::NORMAL | A::TANGENT | A::UV0 | A::SKIN | A::MORPH;
+
+        macro_rules! mesh {
+            ($block:ident, $attributes:expr) => {
+                GenericMesh::new(&$block.vertices, &$block.indices, material, $attributes)
+            };
+        }
+
+        Some(match self {
+            Self::BillboardFoliage(block) => mesh!(block, A::POSITION | A::UV0 | A::UV1),
+            Self::CarPaint(block) => mesh!(block, deformable | A::SIZE).with_bone_influences(4),
+            Self::CarPaintSimple(block) => mesh!(block, lit | A::UV0),
+            Self::DeformableWindow(block) => mesh!(block, deformable).with_bone_influences(4),
+            Self::Facade(block) => mesh!(block, lit | A::UV0 | A::UV1 | A::UV2 | A::COLOR)
+                .with_scale(block.attributes.scale, Vec2::splat(1.0), Vec2::splat(1.0)),
+            Self::General(block) => {
+                mesh!(block, general).with_vertex_info(&block.attributes.vertex_info)
+            }
+            Self::Halo(block) => mesh!(block, A::POSITION | A::UV0 | A::UV1 | A::COLOR),
+            Self::Lambert(block) => {
+                mesh!(block, general).with_vertex_info(&block.attributes.vertex_info)
+            }
+            Self::SkinnedGeneral(block) => {
+                let flags = block.attributes.flags;
+                let bone_influences = if flags.contains(SkinnedGeneralFlags::EIGHT_BONE_INFLUENCE) {
+                    8
+                } else {
+                    4
+                };
+                mesh!(block, lit | A::UV0 | A::SKIN)
+                    .with_bone_influences(bone_influences)
+                    .with_skin_batches(&block.skin_batches)
+            }
+            Self::VegetationBark(block) => mesh!(block, general),
+            Self::VegetationFoliage(block) => mesh!(block, general),
+            Self::Window(block) => mesh!(block, general),
+            Self::Unknown { .. } => return None,
+        })
+    }
+}
+
+impl GenericMesh<'_> {
+    fn to_material(&self) -> Material {
+        Material {
+            textures: self.textures.map(|texture| texture.to_owned().into()),
+            primitive_type: self.primitive_type,
+        }
+    }
+
+    fn index_buffer(&self) -> IndexBuffer<u16> {
+        let mut result = IndexBuffer::default();
+        result.extend_from_slice(self.indices);
+        result
+    }
+
+    /// Vertices for a block storing them relative to the scale and extents of `vertex_info`.
+    fn fitted_vertices<T: Vertex + From<GenericVertex>>(
+        &self,
+        vertex_info: &VertexInfo,
+    ) -> VertexBuffer<T> {
+        let uv0_extent = vertex_info.uv0_extent;
+        let uv1_extent = vertex_info.uv1_extent;
+        VertexBuffer(
+            self.vertices
+                .iter()
+                .map(|vertex| {
+                    let mut vertex = vertex.clone();
+                    vertex.position = vertex.position / vertex_info.scale;
+                    vertex.uv0 =
+                        Vec2::new(vertex.uv0.x / uv0_extent.x, vertex.uv0.y / uv0_extent.y);
+                    vertex.uv1 =
+                        Vec2::new(vertex.uv1.x / uv1_extent.x, vertex.uv1.y / uv1_extent.y);
+                    vertex.into()
+                })
+                .collect(),
+        )
+    }
+
+    #[inline]
+    fn vertices<T: Vertex + From<GenericVertex>>(&self) -> VertexBuffer<T> {
+        VertexBuffer(self.vertices.iter().cloned().map(Into::into).collect())
+    }
+}
+
+/// Blocks storing their vertices as they are.
+macro_rules! impl_from_mesh {
+    ($($block:ty),* $(,)?) => {$(
+        impl $block {
+            /// Builds a block out of `mesh`, the reverse of [`RenderBlock::as_mesh`].
+            pub fn from_mesh(mesh: &GenericMesh<'_>) -> Self {
+                Self {
+                    material: mesh.to_material(),
+                    vertices: mesh.vertices(),
+                    indices: mesh.index_buffer(),
+                    ..Default::default()
+                }
+            }
+        }
+    )*};
+}
+
+impl_from_mesh!(
+    BillboardFoliageRenderBlock,
+    CarPaintRenderBlock,
+    CarPaintSimpleRenderBlock,
+    DeformableWindowRenderBlock,
+    HaloRenderBlock,
+    VegetationBarkRenderBlock,
+    VegetationFoliageRenderBlock,
+    WindowRenderBlock,
+);
+
+/// Blocks whose attributes have a [`VertexInfo`] that is fit to the mesh.
+macro_rules! impl_from_mesh_fitted {
+    ($($block:ty => $attributes:ident),* $(,)?) => {$(
+        impl $block {
+            /// Builds a block out of `mesh`, the reverse of [`RenderBlock::as_mesh`], packing
+            /// vertices as [`VertexFormat::I16`] when that loses little precision.
+            pub fn from_mesh(mesh: &GenericMesh<'_>) -> Self {
+                let vertex_info = VertexInfo::fit(&mesh.vertices);
+                Self {
+                    vertices: mesh.fitted_vertices(&vertex_info),
+                    attributes: $attributes {
+                        vertex_info,
+                        ..Default::default()
+                    },
+                    material: mesh.to_material(),
+                    indices: mesh.index_buffer(),
+                    ..Default::default()
+                }
+            }
+        }
+    )*};
+}
+
+impl_from_mesh_fitted!(
+    GeneralRenderBlock => GeneralAttributes,
+    LambertRenderBlock => LambertAttributes,
+);
+
+impl FacadeRenderBlock {
+    /// Builds a block out of `mesh`, the reverse of [`RenderBlock::as_mesh`], packing positions
+    /// as [`VertexFormat::I16`] when that loses little precision.
+    pub fn from_mesh(mesh: &GenericMesh<'_>) -> Self {
+        let scale = position_extent(&mesh.vertices);
+        let vertex_info = if fits_i16(scale, VertexInfo::MAX_POSITION_ERROR) {
+            VertexInfo {
+                format: VertexFormat::I16,
+                scale,
+                ..Default::default()
+            }
+        } else {
+            VertexInfo::default()
+        };
+        Self {
+            vertices: mesh.fitted_vertices(&vertex_info),
+            attributes: FacadeAttributes {
+                vertex_format: vertex_info.format,
+                scale: vertex_info.scale,
+                ..Default::default()
+            },
+            material: mesh.to_material(),
+            indices: mesh.index_buffer(),
+            ..Default::default()
+        }
+    }
+}
+
+impl SkinnedGeneralRenderBlock {
+    /// Bones a skin batch can reference, as vertices store their bone indices in a byte.
+    pub const MAX_BATCH_BONES: usize = 256;
+
+    /// Builds a block out of `mesh`, the reverse of [`RenderBlock::as_mesh`].
+    ///
+    /// When every bone fits in a single skin batch, all indices go in one batch whose bones map
+    /// to themselves, so bone indices of the vertices are kept as they are. Otherwise triangles
+    /// are split in batches of at most [`Self::MAX_BATCH_BONES`] bones, duplicating the vertices
+    /// that batches share.
+    ///
+    /// Fails when a bone, the number of indices or the vertices of the split batches don't fit
+    /// the sizes the block stores them with, or when a mesh that needs splitting isn't a triangle
+    /// list.
+    pub fn from_mesh(mesh: &GenericMesh<'_>) -> Result<Self, RenderBlockError> {
+        let eight_bones = mesh.bone_influences > 4
+            || mesh
+                .vertices
+                .iter()
+                .any(|vertex| vertex.bone_weights[4..].iter().any(|weight| *weight != 0.0));
+        let influences = if eight_bones { 8 } else { 4 };
+        let last_bone = mesh
+            .vertices
+            .iter()
+            .flat_map(|vertex| &vertex.bone_indices[..influences])
+            .max()
+            .map_or(0, |&bone| bone);
+
+        let mut attributes = SkinnedGeneralAttributes::default();
+        attributes
+            .flags
+            .set(SkinnedGeneralFlags::EIGHT_BONE_INFLUENCE, eight_bones);
+
+        let mut result = Self {
+            attributes,
+            material: mesh.to_material(),
+            ..Default::default()
+        };
+        if let Ok(last_bone) = u8::try_from(last_bone) {
+            result.vertices = mesh.vertices();
+            result.skin_batches = VertexBuffer(vec![SkinBatch {
+                size: index_count(mesh.indices)?,
+                offset: 0,
+                bone_indices: (0..=u16::from(last_bone)).collect(),
+            }]);
+            result.indices = mesh.index_buffer();
+        } else {
+            let (vertices, skin_batches, indices) = split_skin_batches(mesh, influences)?;
+            result.vertices = VertexBuffer(vertices.into_iter().map(Into::into).collect());
+            result.skin_batches = VertexBuffer(skin_batches);
+            result.indices.extend_from_slice(&indices);
+        }
+        Ok(result)
+    }
+}
+
+/// Number of `indices`, as skin batches store it.
+fn index_count(indices: &[u16]) -> Result<u32, RenderBlockError> {
+    let Ok(count) = u32::try_from(indices.len()) else {
+        return Err(RenderBlockError::InvalidArrayLength);
+    };
+    Ok(count)
+}
+
+/// Vertices, skin batches and indices of a skinned block.
+type SkinnedMesh = (Vec<GenericVertex>, Vec<SkinBatch>, Vec<u16>);
+
+/// Splits the triangles of `mesh` in skin batches of at most
+/// [`SkinnedGeneralRenderBlock::MAX_BATCH_BONES`] bones, giving each batch its own copy of the
+/// vertices it draws with bone indices into the palette of the batch.
+fn split_skin_batches(
+    mesh: &GenericMesh<'_>,
+    influences: usize,
+) -> Result<SkinnedMesh, RenderBlockError> {
+    // Only lists can be split at any triangle
+    if !matches!(
+        mesh.primitive_type,
+        PrimitiveType::TriangleList | PrimitiveType::IndexedTriangleList
+    ) {
+        return Err(RenderBlockError::UnsupportedPrimitiveType {
+            primitive_type: mesh.primitive_type,
+        });
+    }
+
+    let mut vertices = Vec::new();
+    let mut batches = Vec::new();
+    let mut indices = Vec::new();
+
+    let mut batch = SkinBatch {
+        size: 0,
+        offset: 0,
+        bone_indices: Vec::new(),
+    };
+    // Palette index of each bone, and block vertex of each mesh vertex, in the current batch
+    let mut palette = HashMap::new();
+    let mut batch_vertices = HashMap::new();
+
+    let weighted_bones = |vertex: &GenericVertex| {
+        vertex.bone_indices[..influences]
+            .iter()
+            .zip(vertex.bone_weights)
+            .filter(|(_, weight)| *weight != 0.0)
+            .map(|(bone, _)| *bone)
+            .collect::<Vec<_>>()
+    };
+
+    for triangle in mesh.indices.chunks(3) {
+        let mut bones: Vec<u32> = triangle
+            .iter()
+            .filter_map(|&index| mesh.vertices.get(usize::from(index)))
+            .flat_map(weighted_bones)
+            .collect();
+        bones.sort_unstable();
+        bones.dedup();
+
+        let new_bones = bones
+            .iter()
+            .filter(|bone| !palette.contains_key(*bone))
+            .count();
+        if palette.len() + new_bones > SkinnedGeneralRenderBlock::MAX_BATCH_BONES {
+            batch.size = index_count(&indices)? - batch.offset;
+            let offset = batch.size + batch.offset;
+            batches.push(std::mem::replace(
+                &mut batch,
+                SkinBatch {
+                    size: 0,
+                    offset,
+                    bone_indices: Vec::new(),
+                },
+            ));
+            palette.clear();
+            batch_vertices.clear();
+        }
+        for bone in bones {
+            if let Entry::Vacant(entry) = palette.entry(bone) {
+                let Ok(palette_bone) = u16::try_from(bone) else {
+                    return Err(RenderBlockError::InvalidBoneIndex { bone });
+                };
+                entry.insert(batch.bone_indices.len() as u32);
+                batch.bone_indices.push(palette_bone);
+            }
+        }
+
+        for &index in triangle {
+            let vertex = if let Some(&vertex) = batch_vertices.get(&index) {
+                vertex
+            } else {
+                let mut vertex = mesh
+                    .vertices
+                    .get(usize::from(index))
+                    .cloned()
+                    .unwrap_or_default();
+                for (bone, weight) in vertex.bone_indices.iter_mut().zip(vertex.bone_weights) {
+                    *bone = if weight == 0.0 {
+                        0
+                    } else {
+                        palette.get(&*bone).copied().unwrap_or(0)
+                    };
+                }
+                let Ok(block_vertex) = u16::try_from(vertices.len()) else {
+                    return Err(RenderBlockError::TooManyVertices {
+                        count: vertices.len() + 1,
+                    });
+                };
+                vertices.push(vertex);
+                batch_vertices.insert(index, block_vertex);
+                block_vertex
+            };
+            indices.push(vertex);
+        }
+    }
+
+    batch.size = index_count(&indices)? - batch.offset;
+    batches.push(batch);
+    Ok((vertices, batches, indices))
+}
@@ -3,7 +3,7 @@ use std::io::{Read, Seek, Write};
 use thiserror::Error;
 
 use crate::math::Vec3;
-use binrw::{binrw, BinRead, BinWrite};
+use binrw::{binrw, BinRead, BinResult, BinWrite};
 
 mod render_block;
 pub use render_block::*;
@@ -14,6 +14,9 @@ pub use common::*;
 mod vertex_format;
 pub use vertex_format::*;
 
+mod mesh;
+pub use mesh::*;
+
 #[derive(Error, Debug)]
 pub enum RenderBlockError {
     #[error("invalid vertex format (expected {expected:?}, found {found:?}")]
@@ -25,6 +28,14 @@ pub enum RenderBlockError {
     InvalidArrayLength,
     #[error("invalid block footer")]
     InvalidBlockFooter,
+    #[error("unknown block {type_hash:#010x} can not change endianness")]
+    UnknownBlockEndian { type_hash: u32 },
+    #[error("bone {bone} can not be referenced by a skin batch")]
+    InvalidBoneIndex { bone: u32 },
+    #[error("{count} vertices can not be referenced by 16 bit indices")]
+    TooManyVertices { count: usize },
+    #[error("{primitive_type:?} meshes can not be split in skin batches")]
+    UnsupportedPrimitiveType { primitive_type: PrimitiveType },
 }
 
 #[binrw]
@@ -36,6 +47,23 @@ pub enum Endian {
     Big,
 }
 
+impl Endian {
+    /// Peeks at the magic of a model, leaving `reader` where it was.
+    pub fn detect<R: Read + Seek>(reader: &mut R) -> BinResult<Self> {
+        crate::peek(reader, Self::read_ne)
+    }
+}
+
+impl From<Endian> for binrw::Endian {
+    #[inline]
+    fn from(value: Endian) -> Self {
+        match value {
+            Endian::Little => Self::Little,
+            Endian::Big => Self::Big,
+        }
+    }
+}
+
 #[binrw]
 #[derive(Clone, Debug)]
 pub struct RenderBlockModel {
@@ -53,19 +81,39 @@ pub struct RenderBlockModel {
 }
 
 impl RenderBlockModel {
+    /// Reads a model in either endianness, detected from its magic.
     pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, binrw::Error> {
-        #[cfg(target_endian = "little")]
-        return Self::read_le(reader);
+        Self::read_ne(reader)
+    }
 
-        #[cfg(target_endian = "big")]
-        return Self::read_be(reader);
+    /// Reads a model, failing if it was not written with `endian`.
+    pub fn read_endian<R: Read + Seek>(
+        reader: &mut R,
+        endian: Endian,
+    ) -> Result<Self, binrw::Error> {
+        crate::expect_endian(reader, endian, |model: &Self| model.endian, Self::read)
     }
 
+    /// Writes the model with the endianness in [`RenderBlockModel::endian`].
     pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<(), binrw::Error> {
-        #[cfg(target_endian = "little")]
-        return self.write_le(writer);
+        self.write_options(writer, self.endian.into(), ())
+    }
 
-        #[cfg(target_endian = "big")]
-        return self.write_be(writer);
+    /// Converts the model to be written for the platform using `endian`.
+    ///
+    /// Unknown blocks are kept as raw bytes in the endianness they were read with, so models
+    /// containing any can't be converted.
+    pub fn with_endian(mut self, endian: Endian) -> Result<Self, RenderBlockError> {
+        if endian != self.endian {
+            let unknown = self.blocks.iter().find_map(|block| match block {
+                RenderBlock::Unknown { type_hash, .. } => Some(*type_hash),
+                _ => None,
+            });
+            if let Some(type_hash) = unknown {
+                return Err(RenderBlockError::UnknownBlockEndian { type_hash });
+            }
+        }
+        self.endian = endian;
+        Ok(self)
     }
 }
@@ -11,7 +11,7 @@ pub enum BillboardFoliageVersion {
 }
 
 #[binrw]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct BillboardFoliageRenderBlock {
     pub version: BillboardFoliageVersion,
     pub material: Material,
@@ -19,9 +19,14 @@ pub enum CarPaintVersion {
 
 bitflags! {
     #[binrw]
-    #[br(map = Self::from_bits_truncate)]
+    #[br(map = Self::from_bits_retain)]
     #[bw(map = |&x: &Self| x.bits())]
     #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(transparent)
+    )]
     pub struct CarPaintFlags: u32 {
         const NO_CULLING = 1 << 0;
         const ALPHA_BLENDING = 1 << 1;
@@ -38,6 +43,7 @@ bitflags! {
 
 #[binrw]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CarPaintAttributes {
     pub two_tone_colors: [Vec3<f32>; 2],
     pub specular_power: f32,
@@ -117,6 +123,7 @@ impl BinWrite for CarPaintRenderBlock {
         args: Self::Args<'_>,
     ) -> binrw::prelude::BinResult<()> {
         self.version.write_options(writer, endian, ())?;
+        self.attributes.write_options(writer, endian, ())?;
         if self.version != CarPaintVersion::V3 {
             self.deform_table.write_options(writer, endian, args)?;
         }
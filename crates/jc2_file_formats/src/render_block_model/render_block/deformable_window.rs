@@ -17,9 +17,14 @@ pub enum DeformableWindowVersion {
 
 bitflags! {
     #[binrw]
-    #[br(map = Self::from_bits_truncate)]
+    #[br(map = Self::from_bits_retain)]
     #[bw(map = |&x: &Self| x.bits())]
     #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(transparent)
+    )]
     pub struct DeformableWindowFlags: u32 {
         const DARK_WINDOW = 1 << 0;
     }
@@ -27,6 +32,7 @@ bitflags! {
 
 #[binrw]
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeformableWindowAttributes {
     pub flags: DeformableWindowFlags,
 }
@@ -16,9 +16,14 @@ pub enum FacadeVersion {
 
 bitflags! {
     #[binrw]
-    #[br(map = Self::from_bits_truncate)]
+    #[br(map = Self::from_bits_retain)]
     #[bw(map = |&x: &Self| x.bits())]
     #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(transparent)
+    )]
     pub struct FacadeFlags: u32 {
         const NO_CULLING = 1 << 0;
         const ALPHA_BLENDING = 1 << 1;
@@ -30,6 +35,7 @@ bitflags! {
 
 #[binrw]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FacadeAttributes {
     pub channel_mask: Vec4<f32>,
     pub channel_dirt_mask: Vec3<f32>,
@@ -58,7 +64,7 @@ impl Default for FacadeAttributes {
 }
 
 #[binrw]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct FacadeRenderBlock {
     pub version: FacadeVersion,
     pub attributes: FacadeAttributes,
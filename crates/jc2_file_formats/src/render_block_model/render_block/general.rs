@@ -18,9 +18,14 @@ pub enum GeneralVersion {
 
 bitflags! {
     #[binrw]
-    #[br(map = Self::from_bits_truncate)]
+    #[br(map = Self::from_bits_retain)]
     #[bw(map = |&x: &Self| x.bits())]
     #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(transparent)
+    )]
     pub struct GeneralFlags: u32 {
         const NO_CULLING = 1 << 0;
         const ALPHA_BLENDING = 1 << 1;
@@ -41,6 +46,7 @@ bitflags! {
     version: &GeneralVersion
 ))]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GeneralAttributes {
     pub channel_mask: Vec4<f32>,
     pub channel_ambient_occlusion_mask: Vec4<f32>,
@@ -66,7 +72,7 @@ impl Default for GeneralAttributes {
 }
 
 #[binrw]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct GeneralRenderBlock {
     pub version: GeneralVersion,
     #[brw(args(&version.clone()))]
@@ -11,7 +11,7 @@ pub enum HaloVersion {
 }
 
 #[binrw]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct HaloRenderBlock {
     pub version: HaloVersion,
     pub material: Material,
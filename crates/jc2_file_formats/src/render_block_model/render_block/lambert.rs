@@ -16,9 +16,14 @@ pub enum LambertVersion {
 
 bitflags! {
     #[binrw]
-    #[br(map = Self::from_bits_truncate)]
+    #[br(map = Self::from_bits_retain)]
     #[bw(map = |&x: &Self| x.bits())]
     #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(transparent)
+    )]
     pub struct LambertFlags: u32 {
         const ALPHA_BLENDING = 1 << 0;
         const ALPHA_TEST = 1 << 1;
@@ -33,8 +38,11 @@ bitflags! {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LambertAttributes {
     pub vertex_info: VertexInfo,
+    /// Flags as stored. Version 0 blocks use dynamic lights whether or not they store
+    /// [`LambertFlags::USE_DYNAMIC_LIGHTS`].
     pub flags: LambertFlags,
     pub depth_bias: f32,
     pub texture_channel: u8,
@@ -69,9 +77,6 @@ impl BinRead for LambertAttributes {
             result.vertex_info = VertexInfo::read_options(reader, endian, (true,))?;
         }
         result.flags = LambertFlags::read_options(reader, endian, ())?;
-        if version == LambertVersion::V0 {
-            result.flags &= LambertFlags::USE_DYNAMIC_LIGHTS;
-        }
         if version != LambertVersion::V0 {
             result.depth_bias = f32::read_options(reader, endian, ())?;
         }
@@ -101,11 +106,7 @@ impl BinWrite for LambertAttributes {
         if version == LambertVersion::V4 {
             self.vertex_info.write_options(writer, endian, (true,))?;
         }
-        if version != LambertVersion::V0 {
-            self.flags.write_options(writer, endian, ())?;
-        } else {
-            (self.flags & !LambertFlags::USE_DYNAMIC_LIGHTS).write_options(writer, endian, ())?;
-        }
+        self.flags.write_options(writer, endian, ())?;
         if version != LambertVersion::V0 {
             self.depth_bias.write_options(writer, endian, ())?;
         }
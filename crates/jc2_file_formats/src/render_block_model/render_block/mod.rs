@@ -1,6 +1,10 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    io::Read,
+    ops::{Deref, DerefMut},
+};
 
-use binrw::{binrw, BinRead, BinWrite};
+use binrw::{binrw, parser, BinRead, BinResult, BinWrite};
+use jc2_hashing::expand_hashes;
 
 use super::RenderBlockError;
 
@@ -40,74 +44,188 @@ pub use vegetation_foliage::*;
 mod window;
 pub use window::*;
 
+#[expand_hashes]
 #[binrw]
 #[rustfmt::skip]
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug)]
 pub enum RenderBlock {
-    /// HashString::from_str("BillboardFoliage")
-    #[brw(magic(2907872880u32))]
+    #[brw(magic(hash!("BillboardFoliage" = 2907872880u32)))]
     BillboardFoliage(BillboardFoliageRenderBlock),
 
-    // /// HashString::from_str("Box")
-    // #[brw(magic(1097613365u32))]
+    // #[brw(magic(hash!("Box" = 1097613365u32)))]
     // Box(BoxRenderBlock),
 
-    /// HashString::from_str("CarPaint")
-    #[brw(magic(3448970869u32))]
+    #[brw(magic(hash!("CarPaint" = 3448970869u32)))]
     CarPaint(CarPaintRenderBlock),
 
-    /// HashString::from_str("CarPaintSimple")
-    #[brw(magic(2173928592u32))]
+    #[brw(magic(hash!("CarPaintSimple" = 2173928592u32)))]
     CarPaintSimple(CarPaintSimpleRenderBlock),
 
-    /// HashString::from_str("DeformableWindow")
-    #[brw(magic(112326146u32))]
+    #[brw(magic(hash!("DeformableWindow" = 112326146u32)))]
     DeformableWindow(DeformableWindowRenderBlock),
 
-    /// HashString::from_str("Facade")
-    #[brw(magic(3459897279u32))]
+    #[brw(magic(hash!("Facade" = 3459897279u32)))]
     Facade(FacadeRenderBlock),
 
-    /// HashString::from_str("General")
-    #[brw(magic(2807577387u32))]
+    #[brw(magic(hash!("General" = 2807577387u32)))]
     General(GeneralRenderBlock),
 
-    /// HashString::from_str("Halo")
-    #[brw(magic(1708766642u32))]
+    #[brw(magic(hash!("Halo" = 1708766642u32)))]
     Halo(HaloRenderBlock),
 
-    /// HashString::from_str("Lambert")
-    #[brw(magic(3587672800u32))]
+    #[brw(magic(hash!("Lambert" = 3587672800u32)))]
     Lambert(LambertRenderBlock),
 
-    // /// HashString::from_str("Merged")
-    // #[brw(magic(2441454787u32))]
+    // #[brw(magic(hash!("Merged" = 2441454787u32)))]
     // Merged(MergedRenderBlock),
 
-    // /// HashString::from_str("Occluder")
-    // #[brw(magic(709121340u32))]
+    // #[brw(magic(hash!("Occluder" = 709121340u32)))]
     // Occluder(OccluderRenderBlock),
 
-    // /// HashString::from_str("Road")
-    // #[brw(magic(1183865387u32))]
+    // #[brw(magic(hash!("Road" = 1183865387u32)))]
     // Road(RoadRenderBlock),
 
-    /// HashString::from_str("SkinnedGeneral")
-    #[brw(magic(1583709984u32))]
+    #[brw(magic(hash!("SkinnedGeneral" = 1583709984u32)))]
     SkinnedGeneral(SkinnedGeneralRenderBlock),
 
-    /// HashString::from_str("VegetationBark")
-    #[brw(magic(2985890621u32))]
+    #[brw(magic(hash!("VegetationBark" = 2985890621u32)))]
     VegetationBark(VegetationBarkRenderBlock),
 
-    /// HashString::from_str("VegetationFoliage")
-    #[brw(magic(3617096902u32))]
+    #[brw(magic(hash!("VegetationFoliage" = 3617096902u32)))]
     VegetationFoliage(VegetationFoliageRenderBlock),
 
-    /// HashString::from_str("Window")
-    #[brw(magic(1528824822u32))]
+    #[brw(magic(hash!("Window" = 1528824822u32)))]
     Window(WindowRenderBlock),
+
+    /// A block of a type that can't be parsed, kept as is up to its footer so it can be written
+    /// back unchanged. Read on its own, it ends at the first footer followed by the end of the
+    /// stream or the type of a known block.
+    Unknown {
+        type_hash: u32,
+        #[br(parse_with = parse_until_footer, args(None))]
+        raw_bytes: Vec<u8>,
+    },
+}
+
+/// Written after every block.
+const BLOCK_FOOTER: u32 = 2309737967u32;
+
+/// Bytes read at a time while looking for the footer ending a block.
+const CHUNK_SIZE: usize = 0x1000;
+
+/// Reads everything up to the footer ending the block, leaving `reader` right before it.
+///
+/// The footer can also appear inside a block, so one only ends the block if it ends the stream
+/// when no blocks remain after this one, or if the type of a known block follows it. Without
+/// knowing the `remaining` blocks, either ends it. When no footer passes that check, such as when
+/// an unknown block follows, the first one is used.
+#[parser(reader, endian)]
+fn parse_until_footer(remaining: Option<u32>) -> BinResult<Vec<u8>> {
+    let read_u32 = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        match endian {
+            binrw::Endian::Big => u32::from_be_bytes(bytes),
+            binrw::Endian::Little => u32::from_le_bytes(bytes),
+        }
+    };
+    let is_block_end = |bytes: &[u8], footer: usize| {
+        let next = &bytes[footer + 4..];
+        let known = || next.len() >= 4 && RenderBlock::TYPE_HASHES.contains(&read_u32(next));
+        match remaining {
+            Some(0) => next.is_empty(),
+            Some(_) => known(),
+            None => next.is_empty() || known(),
+        }
+    };
+
+    let start = reader.stream_position()?;
+    let mut result = Vec::new();
+    let mut first = None;
+    let mut offset = 0;
+    let mut at_end = false;
+    let end = 'search: loop {
+        // A footer can only be checked once the type after it, or the end of the stream, is read
+        while offset + 8 <= result.len() || (at_end && offset + 4 <= result.len()) {
+            if read_u32(&result[offset..]) == BLOCK_FOOTER {
+                first.get_or_insert(offset);
+                if is_block_end(&result, offset) {
+                    break 'search Some(offset);
+                }
+            }
+            offset += 1;
+        }
+        if at_end {
+            break first;
+        }
+        let read = (&mut *reader)
+            .take(CHUNK_SIZE as u64)
+            .read_to_end(&mut result)?;
+        at_end = read < CHUNK_SIZE;
+    };
+    let Some(end) = end else {
+        return Err(BinError::Custom {
+            pos: reader.stream_position()?,
+            err: Box::new(RenderBlockError::InvalidBlockFooter),
+        });
+    };
+
+    result.truncate(end);
+    reader.seek(std::io::SeekFrom::Start(start + end as u64))?;
+    Ok(result)
+}
+
+#[expand_hashes]
+impl RenderBlock {
+    /// Type hashes of every block type models contain, including those without a layout yet which
+    /// are read as [`RenderBlock::Unknown`].
+    #[rustfmt::skip]
+    pub const TYPE_HASHES: [u32; 16] = [
+        hash!("BillboardFoliage" = 2907872880u32),
+        hash!("Box" = 1097613365u32),
+        hash!("CarPaint" = 3448970869u32),
+        hash!("CarPaintSimple" = 2173928592u32),
+        hash!("DeformableWindow" = 112326146u32),
+        hash!("Facade" = 3459897279u32),
+        hash!("General" = 2807577387u32),
+        hash!("Halo" = 1708766642u32),
+        hash!("Lambert" = 3587672800u32),
+        hash!("Merged" = 2441454787u32),
+        hash!("Occluder" = 709121340u32),
+        hash!("Road" = 1183865387u32),
+        hash!("SkinnedGeneral" = 1583709984u32),
+        hash!("VegetationBark" = 2985890621u32),
+        hash!("VegetationFoliage" = 3617096902u32),
+        hash!("Window" = 1528824822u32),
+    ];
+
+    /// Reads a block of a model with `remaining` blocks after it, leaving `reader` right before
+    /// its footer.
+    ///
+    /// Blocks that don't end at their footer were not parsed correctly, so they are kept as
+    /// [`RenderBlock::Unknown`] instead. As where those end depends on the blocks after them,
+    /// unknown blocks are read again knowing how many remain.
+    fn read_in_model<R: std::io::prelude::Read + std::io::prelude::Seek>(
+        reader: &mut R,
+        endian: binrw::Endian,
+        remaining: u32,
+    ) -> BinResult<Self> {
+        let start = reader.stream_position()?;
+        let block = Self::read_options(reader, endian, ())?;
+        if !matches!(block, Self::Unknown { .. }) {
+            let end = reader.stream_position()?;
+            if u32::read_options(reader, endian, ())? == BLOCK_FOOTER {
+                reader.seek(std::io::SeekFrom::Start(end))?;
+                return Ok(block);
+            }
+        }
+
+        reader.seek(std::io::SeekFrom::Start(start))?;
+        Ok(Self::Unknown {
+            type_hash: u32::read_options(reader, endian, ())?,
+            raw_bytes: parse_until_footer(reader, endian, (Some(remaining),))?,
+        })
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -142,16 +260,15 @@ impl BinRead for RenderBlocks {
     ) -> binrw::prelude::BinResult<Self> {
         let length = u32::read_options(reader, endian, ())?;
         let mut blocks = Vec::with_capacity(length as usize);
-        for _ in 0..length {
-            blocks.push(RenderBlock::read_options(reader, endian, ())?);
-
-            const BLOCK_FOOTER: u32 = 2309737967u32;
+        for index in 0..length {
+            let block = RenderBlock::read_in_model(reader, endian, length - index - 1)?;
             if u32::read_options(reader, endian, ())? != BLOCK_FOOTER {
                 return Err(BinError::Custom {
                     pos: reader.stream_position()?,
                     err: Box::new(RenderBlockError::InvalidBlockFooter),
                 });
             }
+            blocks.push(block);
         }
         Ok(Self(blocks))
     }
@@ -169,8 +286,9 @@ impl BinWrite for RenderBlocks {
     ) -> binrw::prelude::BinResult<()> {
         if let Ok(length) = u32::try_from(self.len()) {
             length.write_options(writer, endian, ())?;
-            for index in self.iter() {
-                index.write_options(writer, endian, ())?;
+            for block in self.iter() {
+                block.write_options(writer, endian, ())?;
+                BLOCK_FOOTER.write_options(writer, endian, ())?;
             }
             Ok(())
         } else {
@@ -18,6 +18,7 @@ pub enum SkinnedGeneralVersion {
 #[binrw]
 #[brw(repr = u16)]
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SkinnedGeneralTechnique {
     #[default]
     Skin,
@@ -30,9 +31,14 @@ pub enum SkinnedGeneralTechnique {
 
 bitflags! {
     #[binrw]
-    #[br(map = Self::from_bits_truncate)]
+    #[br(map = Self::from_bits_retain)]
     #[bw(map = |&x: &Self| x.bits())]
     #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(transparent)
+    )]
     pub struct SkinnedGeneralFlags: u16 {
         const NO_CULLING = 1 << 0;
         const ALPHA_TEST = 1 << 1;
@@ -42,6 +48,7 @@ bitflags! {
     }
 }
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SkinnedGeneralAttributes {
     pub technique: SkinnedGeneralTechnique,
     pub flags: SkinnedGeneralFlags,
@@ -13,9 +13,14 @@ pub enum VegetationBarkVersion {
 
 bitflags! {
     #[binrw]
-    #[br(map = Self::from_bits_truncate)]
+    #[br(map = Self::from_bits_retain)]
     #[bw(map = |&x: &Self| x.bits())]
     #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(transparent)
+    )]
     pub struct VegetationBarkFlags: u32 {
         const USE_LOW_RES_SHADOWS = 1 << 0;
         const USE_WATER_FOG = 1 << 1;
@@ -26,12 +31,13 @@ bitflags! {
 
 #[binrw]
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VegetationBarkAttributes {
     pub flags: VegetationBarkFlags,
 }
 
 #[binrw]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct VegetationBarkRenderBlock {
     pub version: VegetationBarkVersion,
     pub attributes: VegetationBarkAttributes,
@@ -16,9 +16,14 @@ pub enum VegetationFoliageVersion {
 
 bitflags! {
     #[binrw]
-    #[br(map = Self::from_bits_truncate)]
+    #[br(map = Self::from_bits_retain)]
     #[bw(map = |&x: &Self| x.bits())]
     #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(transparent)
+    )]
     pub struct VegetationFoliageFlags: u32 {
         const NO_SPECULAR = 1 << 0;
         const NO_TRANSLUCENCY = 1 << 1;
@@ -32,6 +37,7 @@ bitflags! {
 
 #[binrw]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VegetationFoliageAttributes {
     pub specular_intensity: f32,
     pub specular_power: f32,
@@ -51,7 +57,7 @@ impl Default for VegetationFoliageAttributes {
 }
 
 #[binrw]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct VegetationFoliageRenderBlock {
     pub version: VegetationFoliageVersion,
     pub attributes: VegetationFoliageAttributes,
@@ -13,9 +13,14 @@ pub enum WindowVersion {
 
 bitflags! {
     #[binrw]
-    #[br(map = Self::from_bits_truncate)]
+    #[br(map = Self::from_bits_retain)]
     #[bw(map = |&x: &Self| x.bits())]
     #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd)]
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(transparent)
+    )]
     pub struct WindowFlags: u32 {
         const ANIMATE_TEXTURE = 1 << 0;
         const ONE_SIDED = 1 << 1;
@@ -25,6 +30,7 @@ bitflags! {
 
 #[binrw]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowAttributes {
     pub specular_power: f32,
     pub flags: WindowFlags,
@@ -41,7 +47,7 @@ impl Default for WindowAttributes {
 }
 
 #[binrw]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct WindowRenderBlock {
     pub version: WindowVersion,
     pub attributes: WindowAttributes,
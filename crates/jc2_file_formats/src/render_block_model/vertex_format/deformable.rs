@@ -270,10 +270,10 @@ impl BinWrite for VertexBuffer<LitDeformableVertex> {
                     morph_tangent: vertex.morph_tangent,
                 });
             }
-            positions.write_options(writer, endian, ())?;
-            datas.write_options(writer, endian, ())?;
+            VertexBuffer(positions).write_options(writer, endian, ())?;
+            VertexBuffer(datas).write_options(writer, endian, ())?;
         } else {
-            vertices.write_options(writer, endian, ())?;
+            VertexBuffer(vertices).write_options(writer, endian, ())?;
         }
         Ok(())
     }
@@ -87,8 +87,8 @@ impl BinWrite for VertexBuffer<SkinnedVertex> {
                 uv0: vertex.uv0,
             });
         }
-        positions.write_options(writer, endian, args)?;
-        datas.write_options(writer, endian, ())?;
+        VertexBuffer(positions).write_options(writer, endian, args)?;
+        VertexBuffer(datas).write_options(writer, endian, ())?;
         Ok(())
     }
 }
@@ -248,10 +248,10 @@ impl From<SkinnedVertexPosition> for SkinnedVertex4Position {
             (value.bone_weights[3] * 255.0) as u8,
         ]);
         let bone_indices: u32 = bytemuck::must_cast([
-            value.bone_weights[0] as u8,
-            value.bone_weights[1] as u8,
-            value.bone_weights[2] as u8,
-            value.bone_weights[3] as u8,
+            value.bone_indices[0] as u8,
+            value.bone_indices[1] as u8,
+            value.bone_indices[2] as u8,
+            value.bone_indices[3] as u8,
         ]);
         Self {
             position: value.position,
@@ -283,14 +283,14 @@ impl From<SkinnedVertexPosition> for SkinnedVertex8Position {
             (value.bone_weights[7] * 255.0) as u8,
         ]);
         let bone_indices: [u32; 2] = bytemuck::must_cast([
-            value.bone_weights[0] as u8,
-            value.bone_weights[1] as u8,
-            value.bone_weights[2] as u8,
-            value.bone_weights[3] as u8,
-            value.bone_weights[4] as u8,
-            value.bone_weights[5] as u8,
-            value.bone_weights[6] as u8,
-            value.bone_weights[7] as u8,
+            value.bone_indices[0] as u8,
+            value.bone_indices[1] as u8,
+            value.bone_indices[2] as u8,
+            value.bone_indices[3] as u8,
+            value.bone_indices[4] as u8,
+            value.bone_indices[5] as u8,
+            value.bone_indices[6] as u8,
+            value.bone_indices[7] as u8,
         ]);
         Self {
             position: value.position,
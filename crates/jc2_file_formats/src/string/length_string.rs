@@ -17,6 +17,11 @@ impl<T> LengthType for T where
 
 #[binrw]
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "String", into = "String", bound = "")
+)]
 pub struct LengthString<T: LengthType> {
     #[br(parse_with = Self::parse)]
     #[bw(write_with = Self::write)]
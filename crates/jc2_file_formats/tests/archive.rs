@@ -0,0 +1,288 @@
+use std::{io::Cursor, path::PathBuf};
+
+use jc2_file_formats::archive::{
+    ArchiveBuilder, ArchiveBuilderError, ArchiveDiff, ArchiveIssue, ArchivePath, ArchiveTable,
+    ArchiveTableEntry, HashedEntries, NamedHash, Overlay, StreamArchive, StreamArchiveCompression,
+    StreamArchiveIndex, ARCHIVE_ALIGNMENT,
+};
+use jc2_hashing::{HashList, HashString};
+
+fn build(files: &[(&str, &[u8])]) -> (ArchiveTable, Vec<u8>) {
+    let mut builder = ArchiveBuilder::new();
+    for (name, data) in files {
+        builder.insert(*name, *data).expect("file should insert");
+    }
+    let mut arc = Vec::new();
+    let table = builder
+        .write(&mut arc, &mut Cursor::new(Vec::new()), &mut Vec::new())
+        .expect("archive should write");
+    (table, arc)
+}
+
+#[test]
+fn broken_table_reports_issues() {
+    let (mut table, arc) = build(&[("a.bin", &[1; 16]), ("b.bin", &[2; 16])]);
+    assert_eq!(table.verify(arc.len() as u64), []);
+
+    let a = HashString::from_str("a.bin");
+    let past_end = HashString::from_str("c.bin");
+    table.entries.insert(
+        past_end,
+        ArchiveTableEntry {
+            offset: 8,
+            size: ARCHIVE_ALIGNMENT as u32 * 2,
+        },
+    );
+
+    let issues = table.verify(arc.len() as u64);
+    assert!(issues.contains(&ArchiveIssue::Misaligned {
+        key: past_end,
+        offset: 8,
+        alignment: ARCHIVE_ALIGNMENT as u64,
+    }));
+    assert!(issues.contains(&ArchiveIssue::PastEnd {
+        key: past_end,
+        end: 8 + ARCHIVE_ALIGNMENT as u64 * 2,
+        archive_size: arc.len() as u64,
+    }));
+    assert!(issues.contains(&ArchiveIssue::Overlapping {
+        key: past_end,
+        other: a,
+    }));
+}
+
+#[test]
+fn duplicate_hashes_are_found_in_raw_tables() {
+    let (table, _) = build(&[("a.bin", &[1; 16]), ("b.bin", &[2; 16])]);
+    let mut bytes = Cursor::new(Vec::new());
+    table.write(&mut bytes).expect("table should write");
+    bytes.set_position(0);
+    assert_eq!(
+        ArchiveTable::find_duplicates(&mut bytes).expect("table should read"),
+        []
+    );
+
+    // Repeat the first entry, which reading the table collapses into one
+    let mut bytes = bytes.into_inner();
+    let first = bytes[4..16].to_vec();
+    bytes.extend_from_slice(&first);
+    bytes.extend_from_slice(&first);
+    let first_hash = HashString::new(u32::from_le_bytes(
+        first[..4].try_into().expect("hash is 4 bytes"),
+    ));
+    let read = ArchiveTable::read(&mut Cursor::new(&bytes)).expect("table should read");
+    assert_eq!(read.entries.len(), 2);
+    assert_eq!(
+        ArchiveTable::find_duplicates(&mut Cursor::new(&bytes)).expect("table should read"),
+        [ArchiveIssue::Duplicate { key: first_hash }]
+    );
+}
+
+#[test]
+fn diff_lists_changes_by_name() {
+    let (old, old_arc) = build(&[("a.bin", &[1; 16]), ("b.bin", &[2; 16])]);
+    let (new, new_arc) = build(&[("b.bin", &[3; 16]), ("c.bin", &[4; 16])]);
+
+    let diff = ArchiveDiff::tables(
+        &old,
+        &mut Cursor::new(old_arc),
+        &new,
+        &mut Cursor::new(new_arc),
+    )
+    .expect("archives should read");
+
+    let mut hashes = HashList::new();
+    hashes.insert_string("a.bin");
+    hashes.insert_string("b.bin");
+    let named = |name: &str| NamedHash {
+        hash: HashString::from_str(name),
+        name: hashes.find_string(HashString::from_str(name)).cloned(),
+    };
+    let expected = ArchiveDiff {
+        added: vec![named("c.bin")],
+        removed: vec![named("a.bin")],
+        changed: vec![named("b.bin")],
+    };
+    assert_eq!(diff.with_names(&hashes), expected);
+    assert_eq!(expected.added[0].name, None);
+}
+
+#[test]
+fn nested_path_opens_through_each_layer() {
+    let path = ArchivePath::parse(r"archives_win32\pc0.tab\global\general.blz\a.bin")
+        .expect("path should point into an archive");
+    assert_eq!(path.archive, PathBuf::from("archives_win32/pc0.tab"));
+    assert_eq!(
+        path.entries,
+        [PathBuf::from("global/general.blz"), PathBuf::from("a.bin")]
+    );
+    assert_eq!(ArchivePath::parse("global/general.blz"), None);
+
+    let mut stream = StreamArchive::default();
+    stream.insert("a.bin", vec![5u8; 32]);
+    let mut blz = Vec::new();
+    stream
+        .write_compressed(&mut blz, StreamArchiveCompression::Zlib(6))
+        .expect("archive should write");
+    let (table, arc) = build(&[("global/general.blz", &blz), ("other.bin", &[1; 8])]);
+
+    let root = std::env::temp_dir().join(format!("jc2_nested_{}", std::process::id()));
+    std::fs::create_dir_all(root.join("archives_win32")).expect("directory should create");
+    let mut tab = Cursor::new(Vec::new());
+    table.write(&mut tab).expect("table should write");
+    std::fs::write(root.join("archives_win32/pc0.tab"), tab.into_inner()).expect("should write");
+    std::fs::write(root.join("archives_win32/pc0.arc"), arc).expect("should write");
+
+    // Extensions and stream archive entries are matched ignoring case
+    let mixed_case = ArchivePath::parse(r"archives_win32\pc0.tab\global\General.BLZ\A.Bin")
+        .expect("path should point into an archive");
+
+    let opened = path.open(&root);
+    let opened_mixed_case = mixed_case.open(&root);
+    std::fs::remove_dir_all(&root).expect("directory should remove");
+    assert_eq!(opened.expect("entry should open"), [5u8; 32]);
+    assert_eq!(opened_mixed_case.expect("entry should open"), [5u8; 32]);
+}
+
+#[test]
+fn overlay_resolves_by_priority() {
+    let (base, _) = build(&[("a.bin", &[1; 16]), ("b.bin", &[2; 16])]);
+    let (patch, _) = build(&[("a.bin", &[3; 16])]);
+    let (other, _) = build(&[("b.bin", &[4; 16])]);
+
+    let mut overlay = Overlay::new();
+    overlay.insert(0, ("base", base));
+    overlay.insert(10, ("patch", patch));
+    overlay.insert(0, ("other", other));
+
+    let names = |hash: &str| {
+        overlay
+            .candidates(|(_, table)| table.entries.contains_key(&HashString::from_str(hash)))
+            .map(|layer| layer.value.0)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names("a.bin"), ["patch", "base"]);
+    // Layers of equal priority keep the order they were inserted in
+    assert_eq!(names("b.bin"), ["base", "other"]);
+    assert_eq!(names("c.bin"), [] as [&str; 0]);
+}
+
+#[test]
+fn overlay_matches_stream_archives_like_tables() {
+    let mut stream = StreamArchive::default();
+    stream.insert(r"Models\Arve.RBM", vec![1u8; 8]);
+    let hash = ArchiveBuilder::new()
+        .insert("models/arve.rbm", [1u8; 8])
+        .expect("file should insert");
+
+    let mut written = Cursor::new(Vec::new());
+    stream.write(&mut written).expect("archive should write");
+    written.set_position(0);
+    let index = StreamArchiveIndex::read(&mut written).expect("index should read");
+
+    let mut overlay = Overlay::new();
+    overlay.insert(0, HashedEntries::from(stream));
+    let layer = overlay.resolve(hash).expect("entry should resolve");
+    assert_eq!(layer.value.entries.len(), 1);
+    assert!(overlay
+        .resolve(HashString::from_str(r"Models\Arve.RBM"))
+        .is_none());
+
+    let mut overlay = Overlay::new();
+    overlay.insert(0, HashedEntries::from(index));
+    assert!(overlay.resolve(hash).is_some());
+    assert_eq!(
+        overlay.values().next().map(HashedEntries::hashes),
+        Some(&[hash].into())
+    );
+}
+
+#[test]
+fn builder_refuses_hash_collisions() {
+    let mut builder = ArchiveBuilder::new();
+    builder
+        .insert(r"models\a.bin", [1u8; 4])
+        .expect("file should insert");
+
+    // The same file name in another directory hashes the same, so it must hold the same data
+    let error = builder
+        .insert("other/A.BIN", [2u8; 4])
+        .expect_err("different contents should collide");
+    assert!(matches!(
+        error,
+        ArchiveBuilderError::HashCollision { ref name, ref existing }
+            if name == "other/A.BIN" && existing == r"models\a.bin"
+    ));
+    assert_eq!(builder.len(), 1);
+
+    let hash = builder
+        .insert("other/A.BIN", [1u8; 4])
+        .expect("same contents should insert");
+    assert_eq!(hash, HashString::from_str("a.bin"));
+    assert_eq!(builder.len(), 1);
+    assert_eq!(builder.file_list(), "models\\a.bin\nother/A.BIN\n");
+}
+
+#[test]
+fn builder_inserts_a_name_once() {
+    let mut builder = ArchiveBuilder::new();
+    for _ in 0..2 {
+        builder
+            .insert("a.bin", [1u8; 4])
+            .expect("file should insert");
+    }
+    assert_eq!(builder.len(), 1);
+    assert_eq!(builder.file_list(), "a.bin\n");
+
+    // Inserting a name again with new data is a collision rather than a replacement
+    assert!(builder.insert("a.bin", [2u8; 4]).is_err());
+    let table = builder.table().expect("table should lay out");
+    assert_eq!(table.entries.len(), 1);
+}
+
+#[test]
+fn builder_aligns_and_dedups_files() {
+    let files: [(&str, &[u8]); 3] = [
+        ("a.bin", &[1; 3]),
+        ("b.bin", &[2; ARCHIVE_ALIGNMENT + 1]),
+        ("c.bin", &[1; 3]),
+    ];
+    let builder = |dedup: bool| {
+        let mut builder = ArchiveBuilder::new().with_dedup(dedup);
+        for (name, data) in files {
+            builder.insert(name, data).expect("file should insert");
+        }
+        builder
+    };
+
+    for dedup in [false, true] {
+        let builder = builder(dedup);
+        let mut arc = Vec::new();
+        let mut tab = Cursor::new(Vec::new());
+        let mut file_list = Vec::new();
+        let table = builder
+            .write(&mut arc, &mut tab, &mut file_list)
+            .expect("archive should write");
+
+        assert_eq!(arc.len() % ARCHIVE_ALIGNMENT, 0);
+        assert_eq!(table.verify(arc.len() as u64), []);
+        for (name, data) in files {
+            let entry = &table.entries[&HashString::from_str(name)];
+            assert_eq!(entry.offset as usize % ARCHIVE_ALIGNMENT, 0);
+            let start = entry.offset as usize;
+            assert_eq!(&arc[start..start + entry.size as usize], data);
+        }
+        assert_eq!(file_list, b"a.bin\nb.bin\nc.bin\n");
+
+        // Laying out the table alone places files where writing them does
+        let laid_out = builder.table().expect("table should lay out");
+        for (hash, entry) in &table.entries {
+            assert_eq!(laid_out.entries[hash].offset, entry.offset);
+        }
+
+        let offset = |name: &str| table.entries[&HashString::from_str(name)].offset;
+        assert_eq!(offset("a.bin") == offset("c.bin"), dedup);
+        let expected_blocks = if dedup { 3 } else { 4 };
+        assert_eq!(arc.len(), expected_blocks * ARCHIVE_ALIGNMENT);
+    }
+}
@@ -0,0 +1,39 @@
+use std::io::Cursor;
+
+use jc2_file_formats::{
+    archive::{StreamArchive, StreamArchiveCompression},
+    format::{detect_format, FileFormat, FormatError, FormatRegistry},
+};
+
+#[test]
+fn compressed_archive_is_detected_and_parsed() {
+    let mut archive = StreamArchive::default();
+    archive.insert("a.bin", vec![1u8; 64]);
+
+    let mut raw = Cursor::new(Vec::new());
+    archive.write(&mut raw).expect("archive should write");
+    let raw = raw.into_inner();
+    let mut compressed = Vec::new();
+    archive
+        .write_compressed(&mut compressed, StreamArchiveCompression::Zlib(6))
+        .expect("archive should write");
+
+    assert_eq!(detect_format(&raw), FileFormat::StreamArchive);
+    assert_eq!(detect_format(&compressed), FileFormat::Zlib);
+    assert_eq!(
+        detect_format(b"DDS \x7c\x00\x00\x00"),
+        FileFormat::DirectDrawSurface
+    );
+    assert_eq!(detect_format(b"\x00\x08\x00"), FileFormat::Unknown);
+
+    let registry = FormatRegistry::default();
+    let parsed = registry.parse(&compressed).expect("archive should parse");
+    assert_eq!(parsed.format, FileFormat::StreamArchive);
+    let parsed: StreamArchive = parsed.downcast().expect("parser should return an archive");
+    assert_eq!(parsed.entries[0].data, [1u8; 64]);
+
+    assert!(matches!(
+        registry.parse(b"BIKi"),
+        Err(FormatError::Unsupported(FileFormat::Bink))
+    ));
+}
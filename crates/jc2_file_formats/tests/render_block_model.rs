@@ -0,0 +1,832 @@
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use binrw::{BinRead, BinWrite};
+use jc2_file_formats::{
+    math::{Vec2, Vec3, Vec4},
+    render_block_model::{
+        CarPaintRenderBlock, Endian, GeneralRenderBlock, GeneralVersion, GeneralVertex,
+        GenericMesh, GenericVertex, HaloRenderBlock, LambertAttributes, LambertFlags,
+        LambertRenderBlock, LambertVersion, LitDeformableVertex, MeshAttributes, PackedVec4F32,
+        PrimitiveType, RenderBlock, RenderBlockError, RenderBlockModel, RenderBlocks, SkinBatch,
+        SkinnedGeneralFlags, SkinnedGeneralRenderBlock, SkinnedVertex, VertexFormat,
+    },
+};
+
+const BLOCK_FOOTER: u32 = 2309737967;
+
+/// Builds a little endian model by hand out of already serialized blocks.
+fn handmade_model(blocks: &[(u32, &[u8])]) -> Vec<u8> {
+    let mut result = Vec::new();
+    result.extend_from_slice(b"\x05\x00\x00\x00RBMDL");
+    for value in [1u32, 13, 0] {
+        result.extend_from_slice(&value.to_le_bytes());
+    }
+    for value in [-1.0f32, -1.0, -1.0, 1.0, 1.0, 1.0] {
+        result.extend_from_slice(&value.to_le_bytes());
+    }
+    result.extend_from_slice(&(blocks.len() as u32).to_le_bytes());
+    for (type_hash, payload) in blocks {
+        result.extend_from_slice(&type_hash.to_le_bytes());
+        result.extend_from_slice(payload);
+        result.extend_from_slice(&BLOCK_FOOTER.to_le_bytes());
+    }
+    result
+}
+
+#[test]
+fn unknown_block_round_trips() {
+    // A halo without vertices: version, 8 empty texture names, primitive type and two empty buffers
+    let mut halo = vec![0u8];
+    halo.extend_from_slice(&[0u8; 4 * 8 + 4 + 4 + 4]);
+    let unknown = [1u8, 2, 3, 4, 5, 6, 7];
+    let bytes = handmade_model(&[(0x1234_5678, &unknown), (1708766642, &halo)]);
+
+    let model = RenderBlockModel::read(&mut Cursor::new(&bytes)).expect("model should read");
+    assert_eq!(model.blocks.len(), 2);
+    match &model.blocks[0] {
+        RenderBlock::Unknown {
+            type_hash,
+            raw_bytes,
+        } => assert_eq!(
+            (*type_hash, raw_bytes.as_slice()),
+            (0x1234_5678, &unknown[..])
+        ),
+        block => panic!("expected an unknown block, found {block:?}"),
+    }
+    assert!(matches!(model.blocks[1], RenderBlock::Halo(_)));
+
+    let mut written = Cursor::new(Vec::new());
+    model.write(&mut written).expect("model should write");
+    assert_eq!(written.into_inner(), bytes);
+
+    // Raw bytes can't be swapped, so only the endianness they were read with is accepted
+    assert!(matches!(
+        model.clone().with_endian(Endian::Big),
+        Err(RenderBlockError::UnknownBlockEndian {
+            type_hash: 0x1234_5678
+        })
+    ));
+    assert!(model.with_endian(Endian::Little).is_ok());
+}
+
+#[test]
+fn unknown_block_may_contain_footer() {
+    let mut halo = vec![0u8];
+    halo.extend_from_slice(&[0u8; 4 * 8 + 4 + 4 + 4]);
+    let mut unknown = vec![1u8, 2, 3];
+    unknown.extend_from_slice(&BLOCK_FOOTER.to_le_bytes());
+    unknown.extend_from_slice(&[4u8, 5, 6, 7]);
+
+    // Followed by a known block, and as the last block
+    for blocks in [
+        [(0x1234_5678, &unknown[..]), (1708766642, &halo[..])],
+        [(1708766642, &halo[..]), (0x1234_5678, &unknown[..])],
+    ] {
+        let bytes = handmade_model(&blocks);
+        let model = RenderBlockModel::read(&mut Cursor::new(&bytes)).expect("model should read");
+        assert_eq!(model.blocks.len(), 2);
+        let raw_bytes = model.blocks.iter().find_map(|block| match block {
+            RenderBlock::Unknown { raw_bytes, .. } => Some(raw_bytes.as_slice()),
+            _ => None,
+        });
+        assert_eq!(raw_bytes, Some(&unknown[..]));
+        assert_eq!(write_model(&model), bytes);
+    }
+
+    // Read on its own, a block can end at a footer followed by the end of the stream
+    let mut bytes = 0x1234_5678u32.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&unknown);
+    bytes.extend_from_slice(&BLOCK_FOOTER.to_le_bytes());
+    let block = RenderBlock::read_le(&mut Cursor::new(&bytes)).expect("block should read");
+    assert!(matches!(
+        block,
+        RenderBlock::Unknown { type_hash: 0x1234_5678, ref raw_bytes } if raw_bytes == &unknown
+    ));
+}
+
+#[test]
+fn blocks_without_layout_are_kept_raw() {
+    // Box, merged, occluder and road blocks have no verified layout, so they go through the same
+    // path as blocks of an unknown type and are found to end at the type of the next one
+    let mut halo = vec![0u8];
+    halo.extend_from_slice(&[0u8; 4 * 8 + 4 + 4 + 4]);
+    // The road block spans several reads, with a footer inside it crossing from one to the next
+    let mut road = vec![1u8; 0x1000 - 2];
+    road.extend_from_slice(&BLOCK_FOOTER.to_le_bytes());
+    road.extend_from_slice(&[4u8; 0x1000]);
+    let occluder = [5u8, 6, 7, 8];
+    let bytes = handmade_model(&[
+        (1183865387, &road),
+        (709121340, &occluder),
+        (1708766642, &halo),
+    ]);
+
+    let model = RenderBlockModel::read(&mut Cursor::new(&bytes)).expect("model should read");
+    let raw_blocks: Vec<_> = model
+        .blocks
+        .iter()
+        .filter_map(|block| match block {
+            RenderBlock::Unknown {
+                type_hash,
+                raw_bytes,
+            } => Some((*type_hash, raw_bytes.as_slice())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        raw_blocks,
+        [(1183865387, &road[..]), (709121340, &occluder[..])]
+    );
+    assert!(matches!(model.blocks[2], RenderBlock::Halo(_)));
+    assert_eq!(write_model(&model), bytes);
+}
+
+#[test]
+fn block_types_are_listed() {
+    for block in [
+        RenderBlock::BillboardFoliage(Default::default()),
+        RenderBlock::CarPaint(Default::default()),
+        RenderBlock::CarPaintSimple(Default::default()),
+        RenderBlock::DeformableWindow(Default::default()),
+        RenderBlock::Facade(Default::default()),
+        RenderBlock::General(Default::default()),
+        RenderBlock::Halo(Default::default()),
+        RenderBlock::Lambert(Default::default()),
+        RenderBlock::SkinnedGeneral(Default::default()),
+        RenderBlock::VegetationBark(Default::default()),
+        RenderBlock::VegetationFoliage(Default::default()),
+        RenderBlock::Window(Default::default()),
+    ] {
+        let mut bytes = Cursor::new(Vec::new());
+        block.write_le(&mut bytes).expect("block should write");
+        bytes.set_position(0);
+        let type_hash = u32::read_le(&mut bytes).expect("block should have a type");
+        assert!(
+            RenderBlock::TYPE_HASHES.contains(&type_hash),
+            "{type_hash} is not listed"
+        );
+    }
+}
+
+fn general_vertex(i: f32) -> GeneralVertex {
+    GeneralVertex {
+        position: Vec3::from([i, -i, 0.5 * i]),
+        uv0: Vec2 {
+            x: 0.25 * i,
+            y: 0.5,
+        },
+        uv1: Vec2 {
+            x: 0.75,
+            y: -0.25 * i,
+        },
+        normal: Vec3::from([0.0, 1.0, 0.0]),
+        tangent: Vec4 {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        },
+        color: Vec4 {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+            w: 1.0,
+        },
+    }
+}
+
+/// One block of every parsed type, with a few vertices where the block has any.
+fn fixture_blocks() -> Vec<RenderBlock> {
+    let mut general = GeneralRenderBlock::default();
+    general.attributes.vertex_info.format = VertexFormat::I16;
+    general.attributes.vertex_info.scale = 4.0;
+    general.attributes.vertex_info.uv0_extent = Vec2 { x: 2.0, y: 3.0 };
+    general.attributes.vertex_info.uv1_extent = Vec2 { x: 5.0, y: 7.0 };
+    general
+        .vertices
+        .extend((0..3).map(|i| general_vertex(i as f32)));
+    general.indices.extend([0, 1, 2]);
+
+    let mut general_v1 = GeneralRenderBlock {
+        version: GeneralVersion::V1,
+        ..Default::default()
+    };
+    general_v1
+        .vertices
+        .extend((0..3).map(|i| general_vertex(i as f32)));
+    general_v1.indices.extend([2, 1, 0]);
+
+    let mut lambert = LambertRenderBlock::default();
+    lambert
+        .vertices
+        .extend((0..3).map(|i| general_vertex(i as f32)));
+    lambert.indices.extend([0, 1, 2]);
+
+    let mut car_paint = CarPaintRenderBlock::default();
+    car_paint.attributes.two_tone_colors =
+        [Vec3::from([1.0, 0.0, 0.0]), Vec3::from([0.0, 0.0, 1.0])];
+    car_paint.attributes.reflection_multiplier = 0.5;
+    car_paint.vertices.push(LitDeformableVertex {
+        position: Vec3::from([1.0, 2.0, 3.0]),
+        bone_weights: [1.0, 0.0, 0.0, 0.0],
+        ..Default::default()
+    });
+    car_paint.indices.extend([0, 0, 0]);
+
+    let mut skinned_general = SkinnedGeneralRenderBlock::default();
+    skinned_general.vertices.push(SkinnedVertex {
+        position: Vec3::from([0.0, 1.0, 0.0]),
+        bone_weights: [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+        bone_indices: [5, 2, 9, 1, 0, 0, 0, 0],
+        ..Default::default()
+    });
+    skinned_general.indices.extend([0, 0, 0]);
+    skinned_general.skin_batches.push(SkinBatch {
+        size: 3,
+        offset: 0,
+        bone_indices: (10..20).collect(),
+    });
+
+    let mut skinned_eight = SkinnedGeneralRenderBlock::default();
+    skinned_eight.attributes.flags |= SkinnedGeneralFlags::EIGHT_BONE_INFLUENCE;
+    skinned_eight.vertices.push(SkinnedVertex {
+        position: Vec3::from([0.0, 0.0, 1.0]),
+        bone_weights: [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+        bone_indices: [8, 7, 6, 5, 4, 3, 2, 255],
+        ..Default::default()
+    });
+    skinned_eight.indices.extend([0, 0, 0]);
+
+    vec![
+        RenderBlock::General(general),
+        RenderBlock::General(general_v1),
+        RenderBlock::Lambert(lambert),
+        RenderBlock::CarPaint(car_paint),
+        RenderBlock::CarPaintSimple(Default::default()),
+        RenderBlock::DeformableWindow(Default::default()),
+        RenderBlock::SkinnedGeneral(skinned_general),
+        RenderBlock::BillboardFoliage(Default::default()),
+        RenderBlock::Facade(Default::default()),
+        RenderBlock::Halo(Default::default()),
+        RenderBlock::VegetationBark(Default::default()),
+        RenderBlock::VegetationFoliage(Default::default()),
+        RenderBlock::Window(Default::default()),
+        RenderBlock::SkinnedGeneral(skinned_eight),
+    ]
+}
+
+fn fixture_model(endian: Endian) -> RenderBlockModel {
+    let mut blocks = RenderBlocks::default();
+    blocks.extend(fixture_blocks());
+    RenderBlockModel {
+        endian,
+        version: Vec3 { x: 1, y: 13, z: 0 },
+        min: Vec3::from([-1.0, -1.0, -1.0]),
+        max: Vec3::from([1.0, 1.0, 1.0]),
+        blocks,
+    }
+}
+
+fn write_model(model: &RenderBlockModel) -> Vec<u8> {
+    let mut writer = Cursor::new(Vec::new());
+    model.write(&mut writer).expect("model should write");
+    writer.into_inner()
+}
+
+#[test]
+fn fixture_blocks_round_trip() {
+    for endian in [Endian::Little, Endian::Big] {
+        // The first write quantizes packed values, after which reading and writing is exact
+        let bytes = write_model(&fixture_model(endian));
+        let model = RenderBlockModel::read(&mut Cursor::new(&bytes)).expect("model should read");
+        assert_eq!(model.blocks.len(), fixture_blocks().len());
+        assert!(!model
+            .blocks
+            .iter()
+            .any(|block| matches!(block, RenderBlock::Unknown { .. })));
+        if let Err(message) = compare(&model, &bytes, &write_model(&model)) {
+            panic!("{endian:?} fixture: {message}");
+        }
+    }
+}
+
+#[test]
+fn attributes_survive_round_trip() {
+    let bytes = write_model(&fixture_model(Endian::Little));
+    let model = RenderBlockModel::read(&mut Cursor::new(&bytes)).expect("model should read");
+
+    let RenderBlock::General(general) = &model.blocks[0] else {
+        panic!("expected a general block, found {:?}", model.blocks[0]);
+    };
+    let vertex_info = &general.attributes.vertex_info;
+    assert_eq!(vertex_info.format, VertexFormat::I16);
+    assert_eq!(vertex_info.uv0_extent, Vec2 { x: 2.0, y: 3.0 });
+    assert_eq!(vertex_info.uv1_extent, Vec2 { x: 5.0, y: 7.0 });
+
+    let RenderBlock::CarPaint(car_paint) = &model.blocks[3] else {
+        panic!("expected a car paint block, found {:?}", model.blocks[3]);
+    };
+    assert_eq!(
+        car_paint.attributes.two_tone_colors,
+        [Vec3::from([1.0, 0.0, 0.0]), Vec3::from([0.0, 0.0, 1.0])]
+    );
+    assert_eq!(car_paint.attributes.reflection_multiplier, 0.5);
+
+    let RenderBlock::SkinnedGeneral(skinned) = &model.blocks[6] else {
+        panic!(
+            "expected a skinned general block, found {:?}",
+            model.blocks[6]
+        );
+    };
+    assert_eq!(skinned.vertices[0].bone_indices, [5, 2, 9, 1, 0, 0, 0, 0]);
+    let Some(RenderBlock::SkinnedGeneral(skinned)) = model.blocks.last() else {
+        panic!(
+            "expected a skinned general block, found {:?}",
+            model.blocks.last()
+        );
+    };
+    assert_eq!(skinned.vertices[0].bone_indices, [8, 7, 6, 5, 4, 3, 2, 255]);
+    assert_eq!(skinned.vertices[0].bone_weights[7], 1.0);
+}
+
+#[test]
+fn lambert_v0_keeps_stored_dynamic_lights() {
+    // Version 0 always uses dynamic lights, so the flag is kept only as stored
+    for flags in [
+        LambertFlags::TWO_SIDED,
+        LambertFlags::TWO_SIDED | LambertFlags::USE_DYNAMIC_LIGHTS,
+    ] {
+        let bytes = flags.bits().to_le_bytes();
+        let attributes = LambertAttributes::read_options(
+            &mut Cursor::new(&bytes),
+            binrw::Endian::Little,
+            (&LambertVersion::V0,),
+        )
+        .expect("attributes should read");
+        assert_eq!(attributes.flags, flags);
+
+        let mut writer = Cursor::new(Vec::new());
+        attributes
+            .write_options(&mut writer, binrw::Endian::Little, (&LambertVersion::V0,))
+            .expect("attributes should write");
+        assert_eq!(writer.into_inner(), bytes, "{flags:?}");
+    }
+}
+
+#[test]
+fn packed_colors_round_trip() {
+    let color = Vec4 {
+        x: 1.0,
+        y: 0.5,
+        z: 0.25,
+        w: 0.0,
+    };
+    let unpacked = Vec4::<f32>::from(PackedVec4F32::from(color));
+    for (unpacked, expected) in <[f32; 4]>::from(unpacked)
+        .into_iter()
+        .zip(<[f32; 4]>::from(color))
+    {
+        assert!(
+            (unpacked - expected).abs() < 1.0 / 255.0,
+            "{unpacked} != {expected}"
+        );
+    }
+
+    // Each channel has its own byte, and values outside 0..=1 are clamped
+    let packed = PackedVec4F32::from(Vec4 {
+        x: 2.0,
+        y: -1.0,
+        z: 0.0,
+        w: 1.0,
+    });
+    let mut writer = Cursor::new(Vec::new());
+    packed.write_le(&mut writer).expect("color should write");
+    assert_eq!(writer.into_inner(), [0xFF, 0x00, 0x00, 0xFF]);
+}
+
+#[test]
+fn blocks_view_as_generic_mesh() {
+    let blocks = fixture_blocks();
+
+    let general = blocks[0].as_mesh().expect("general blocks have a mesh");
+    assert_eq!(general.indices, &[0, 1, 2]);
+    assert_eq!(general.positions()[1], Vec3::from([4.0, -4.0, 2.0]));
+    assert_eq!(
+        general.uvs(0).expect("general blocks have uvs")[1],
+        Vec2 { x: 0.5, y: 1.5 }
+    );
+    assert!(general.colors().is_some());
+    assert!(general.bone_weights().is_none());
+
+    let foliage = blocks[7].as_mesh().expect("billboard foliage has a mesh");
+    assert!(foliage.positions().is_empty());
+    assert!(foliage.normals().is_none());
+    assert_eq!(foliage.textures, [""; 8]);
+
+    let skinned = blocks[6].as_mesh().expect("skinned blocks have a mesh");
+    assert_eq!(skinned.bone_influences, 4);
+    assert_eq!(
+        skinned.bone_weights().expect("skinned blocks have weights")[0][0],
+        1.0
+    );
+    // Bone indices go through the palette of the skin batch
+    assert_eq!(
+        skinned.bone_indices().expect("skinned blocks have indices")[0][..4],
+        [15, 12, 19, 11]
+    );
+
+    let unknown = RenderBlock::Unknown {
+        type_hash: 0,
+        raw_bytes: Vec::new(),
+    };
+    assert!(unknown.as_mesh().is_none());
+}
+
+#[test]
+fn blocks_build_from_generic_mesh() {
+    let blocks = fixture_blocks();
+    let mesh = blocks[1].as_mesh().expect("general blocks have a mesh");
+
+    // Small meshes are packed, with the scale and extents fit to the data
+    let general = GeneralRenderBlock::from_mesh(&mesh);
+    let vertex_info = &general.attributes.vertex_info;
+    assert_eq!(vertex_info.format, VertexFormat::I16);
+    assert_eq!(vertex_info.scale, 2.0);
+    assert_eq!(vertex_info.uv0_extent, Vec2 { x: 0.5, y: 0.5 });
+    let rebuilt = RenderBlock::General(general);
+    let rebuilt = rebuilt.as_mesh().expect("general blocks have a mesh");
+    assert_eq!(rebuilt.positions(), mesh.positions());
+    assert_eq!(rebuilt.uvs(0), mesh.uvs(0));
+    assert_eq!(rebuilt.indices, mesh.indices);
+
+    // Packing this mesh would lose too much precision
+    let mut large = mesh.clone();
+    large.vertices[2].position = Vec3::from([10000.0, 0.0, 0.0]);
+    let general = GeneralRenderBlock::from_mesh(&large);
+    assert_eq!(general.attributes.vertex_info.format, VertexFormat::F32);
+    assert_eq!(general.attributes.vertex_info.scale, 1.0);
+
+    let mut skinned = blocks[6].as_mesh().expect("skinned blocks have a mesh");
+    skinned.vertices[0].bone_indices = [0, 1, 2, 3, 4, 5, 6, 7];
+    skinned.vertices[0].bone_weights[5] = 0.5;
+    let skinned = SkinnedGeneralRenderBlock::from_mesh(&skinned).expect("mesh should fit");
+    assert!(skinned
+        .attributes
+        .flags
+        .contains(SkinnedGeneralFlags::EIGHT_BONE_INFLUENCE));
+    assert_eq!(
+        skinned.skin_batches[0].bone_indices,
+        (0..8).collect::<Vec<u16>>()
+    );
+    let skinned = write_and_read(RenderBlock::SkinnedGeneral(skinned));
+    let rebuilt = skinned.as_mesh().expect("skinned blocks have a mesh");
+    assert_eq!(rebuilt.bone_influences, 8);
+    assert_eq!(
+        rebuilt.bone_indices().expect("skinned blocks have indices")[0],
+        [0, 1, 2, 3, 4, 5, 6, 7]
+    );
+
+    // Triangles of a mesh with more bones than a batch can reference are split across batches
+    let vertices: Vec<GenericVertex> = (0..302u32)
+        .map(|bone| GenericVertex {
+            position: Vec3::from([bone as f32, 0.0, 0.0]),
+            bone_weights: [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            bone_indices: [bone, 0, 0, 0, 0, 0, 0, 0],
+            ..Default::default()
+        })
+        .collect();
+    let indices: Vec<u16> = (0..300).flat_map(|i| [i, i + 1, i + 2]).collect();
+    let mesh = GenericMesh {
+        vertices,
+        attributes: MeshAttributes::POSITION | MeshAttributes::SKIN,
+        bone_influences: 4,
+        indices: &indices,
+        ..Default::default()
+    };
+    let skinned = SkinnedGeneralRenderBlock::from_mesh(&mesh).expect("mesh should fit");
+    assert_eq!(skinned.skin_batches.len(), 2);
+    assert!(skinned
+        .skin_batches
+        .iter()
+        .all(|batch| batch.bone_indices.len() <= SkinnedGeneralRenderBlock::MAX_BATCH_BONES));
+    let skinned = write_and_read(RenderBlock::SkinnedGeneral(skinned));
+    let rebuilt = skinned.as_mesh().expect("skinned blocks have a mesh");
+    assert_eq!(rebuilt.indices.len(), indices.len());
+    for (&original, &index) in indices.iter().zip(rebuilt.indices) {
+        let original = &mesh.vertices[usize::from(original)];
+        let vertex = &rebuilt.vertices[usize::from(index)];
+        assert_eq!(vertex.position, original.position);
+        assert_eq!(vertex.bone_indices[0], original.bone_indices[0]);
+    }
+
+    // Bones past what a skin batch stores are refused rather than truncated
+    let mut vertices = mesh.vertices.clone();
+    vertices[0].bone_indices[0] = 70000;
+    let mesh = GenericMesh { vertices, ..mesh };
+    assert!(matches!(
+        SkinnedGeneralRenderBlock::from_mesh(&mesh),
+        Err(RenderBlockError::InvalidBoneIndex { bone: 70000 })
+    ));
+
+    // Strips can't be split at arbitrary triangles
+    let mesh = GenericMesh {
+        primitive_type: PrimitiveType::TriangleStrip,
+        ..mesh
+    };
+    assert!(matches!(
+        SkinnedGeneralRenderBlock::from_mesh(&mesh),
+        Err(RenderBlockError::UnsupportedPrimitiveType {
+            primitive_type: PrimitiveType::TriangleStrip
+        })
+    ));
+}
+
+#[test]
+fn halo_colors_survive_from_mesh() {
+    let colors = [
+        [1.0, 0.0, 0.0, 1.0],
+        [0.0, 0.5, 0.25, 0.75],
+        [0.2, 0.4, 0.6, 0.8],
+    ];
+    let vertices: Vec<GenericVertex> = colors
+        .iter()
+        .enumerate()
+        .map(|(index, &color)| GenericVertex {
+            position: Vec3::from([index as f32, 0.0, 0.0]),
+            diffuse_color: color.into(),
+            ..Default::default()
+        })
+        .collect();
+    let mesh = GenericMesh {
+        vertices,
+        attributes: MeshAttributes::POSITION | MeshAttributes::COLOR,
+        indices: &[0, 1, 2],
+        ..Default::default()
+    };
+
+    let halo = write_and_read(RenderBlock::Halo(HaloRenderBlock::from_mesh(&mesh)));
+    let rebuilt = halo.as_mesh().expect("halo blocks have a mesh");
+    assert!(rebuilt.attributes.contains(MeshAttributes::COLOR));
+    for (vertex, expected) in rebuilt.vertices.iter().zip(colors) {
+        let color: [f32; 4] = vertex.diffuse_color.into();
+        for (channel, expected) in color.into_iter().zip(expected) {
+            assert!((channel - expected).abs() < 1.0 / 255.0);
+        }
+    }
+}
+
+/// Writes `block` as the only block of a model and reads it back.
+fn write_and_read(block: RenderBlock) -> RenderBlock {
+    let mut model = fixture_model(Endian::Little);
+    model.blocks.clear();
+    model.blocks.push(block);
+    let bytes = write_model(&model);
+    let mut model = RenderBlockModel::read(&mut Cursor::new(&bytes)).expect("model should read");
+    model.blocks.pop().expect("model should have a block")
+}
+
+/// Header fields of a model and where they end.
+const HEADER_FIELDS: [(&str, usize); 6] = [
+    ("endian", 4),
+    ("magic", 9),
+    ("version", 21),
+    ("min", 33),
+    ("max", 45),
+    ("block count", 49),
+];
+
+fn written_len<T: BinWrite>(value: &T, endian: binrw::Endian, args: T::Args<'_>) -> usize {
+    let mut writer = Cursor::new(Vec::new());
+    value
+        .write_options(&mut writer, endian, args)
+        .expect("field should write");
+    writer.into_inner().len()
+}
+
+/// Fields of a serialized block and where they end, relative to the start of the block.
+///
+/// Blocks write their attributes and material before the vertex buffer, and any skin batches,
+/// index buffer and deform table after it, so the vertex buffer is whatever lies in between.
+fn block_fields(
+    block: &RenderBlock,
+    endian: binrw::Endian,
+    len: usize,
+) -> Vec<(&'static str, usize)> {
+    let mut head = vec![("type hash", 4)];
+    let mut tail = vec![("footer", 4)];
+    let attributes = match block {
+        RenderBlock::General(block) => written_len(&block.attributes, endian, (&block.version,)),
+        RenderBlock::Lambert(block) => written_len(&block.attributes, endian, (&block.version,)),
+        RenderBlock::CarPaint(block) => written_len(&block.attributes, endian, ()),
+        RenderBlock::CarPaintSimple(block) => written_len(&block.attributes, endian, ()),
+        RenderBlock::DeformableWindow(block) => written_len(&block.attributes, endian, ()),
+        RenderBlock::Facade(block) => written_len(&block.attributes, endian, ()),
+        RenderBlock::SkinnedGeneral(block) => written_len(&block.attributes, endian, ()),
+        RenderBlock::VegetationBark(block) => written_len(&block.attributes, endian, ()),
+        RenderBlock::VegetationFoliage(block) => written_len(&block.attributes, endian, ()),
+        RenderBlock::Window(block) => written_len(&block.attributes, endian, ()),
+        RenderBlock::Unknown { .. } => {
+            head.push(("raw bytes", len - 8));
+            return accumulate(head.into_iter().chain(tail));
+        }
+        _ => 0,
+    };
+    head.push(("version", 1));
+    if attributes > 0 {
+        head.push(("attributes", attributes));
+    }
+    if let Some(material) = block.material() {
+        head.push(("material", written_len(material, endian, ())));
+    }
+
+    match block {
+        RenderBlock::CarPaint(block) => {
+            tail.push(("deform table", written_len(&block.deform_table, endian, ())));
+        }
+        RenderBlock::DeformableWindow(block) => {
+            tail.push(("deform table", written_len(&block.deform_table, endian, ())));
+        }
+        _ => {}
+    }
+    let indices = block.as_mesh().map_or(0, |mesh| mesh.indices.len());
+    tail.push(("index buffer", 4 + indices * 2));
+    if let RenderBlock::SkinnedGeneral(block) = block {
+        tail.push(("skin batches", written_len(&block.skin_batches, endian, ())));
+    }
+
+    let known: usize = head.iter().chain(&tail).map(|(_, len)| len).sum();
+    head.push(("vertex buffer", len.saturating_sub(known)));
+    accumulate(head.into_iter().chain(tail.into_iter().rev()))
+}
+
+/// Turns field sizes into where each field ends.
+fn accumulate(fields: impl Iterator<Item = (&'static str, usize)>) -> Vec<(&'static str, usize)> {
+    fields
+        .scan(0, |end, (name, len)| {
+            *end += len;
+            Some((name, *end))
+        })
+        .collect()
+}
+
+/// Names the field of `model` holding `offset` of its serialized bytes.
+fn describe_offset(model: &RenderBlockModel, offset: usize) -> String {
+    if let Some((name, _)) = HEADER_FIELDS.iter().find(|(_, end)| offset < *end) {
+        return format!("header {name}");
+    }
+
+    let endian = model.endian.into();
+    let mut start = HEADER_FIELDS[HEADER_FIELDS.len() - 1].1;
+    for (index, block) in model.blocks.iter().enumerate() {
+        let mut writer = Cursor::new(Vec::new());
+        if block.write_options(&mut writer, endian, ()).is_err() {
+            return format!("block {index}, which can't be written");
+        }
+        let len = writer.get_ref().len() + 4;
+        if offset < start + len {
+            let kind = format!("{block:?}");
+            let kind = kind.split(['(', ' ']).next().unwrap_or_default();
+            let offset = offset - start;
+            let fields = block_fields(block, endian, len);
+            let (field, field_start) = fields
+                .iter()
+                .scan(0, |field_start, &(name, end)| {
+                    let result = (name, *field_start, end);
+                    *field_start = end;
+                    Some(result)
+                })
+                .find(|&(_, _, end)| offset < end)
+                .map_or(("unknown field", 0), |(name, field_start, _)| {
+                    (name, field_start)
+                });
+            return format!(
+                "block {index} ({kind}) {field} at offset {}",
+                offset - field_start
+            );
+        }
+        start += len;
+    }
+    "trailing data".to_owned()
+}
+
+#[test]
+fn offsets_are_described_by_field() {
+    let model = fixture_model(Endian::Little);
+    let bytes = write_model(&model);
+    let describe = |offset| describe_offset(&model, offset);
+    assert_eq!(describe(5), "header magic");
+
+    // The first block is a general block with three vertices and three indices
+    let mut block = Cursor::new(Vec::new());
+    model.blocks[0]
+        .write_options(&mut block, binrw::Endian::Little, ())
+        .expect("block should write");
+    let attributes = 49 + 4 + 1;
+    let material = attributes + 4 * 4 + 4 * 4 + 4 + 4 + (4 + 4 + 4 * 4 + 4 + 4) + 4;
+    let vertices = material + 8 * 4 + 4;
+    let indices = 49 + block.get_ref().len() - (4 + 3 * 2);
+    assert_eq!(describe(49), "block 0 (General) type hash at offset 0");
+    assert_eq!(
+        describe(attributes + 2),
+        "block 0 (General) attributes at offset 2"
+    );
+    assert_eq!(describe(material), "block 0 (General) material at offset 0");
+    assert_eq!(
+        describe(vertices + 4),
+        "block 0 (General) vertex buffer at offset 4"
+    );
+    assert_eq!(
+        describe(indices),
+        "block 0 (General) index buffer at offset 0"
+    );
+    assert_eq!(
+        describe(indices + 10),
+        "block 0 (General) footer at offset 0"
+    );
+    assert!(describe(bytes.len() - 1).ends_with("footer at offset 3"));
+    assert_eq!(describe(bytes.len()), "trailing data");
+}
+
+/// Checks that `written` is `original`, describing the first difference otherwise.
+fn compare(model: &RenderBlockModel, original: &[u8], written: &[u8]) -> Result<(), String> {
+    let offset = original
+        .iter()
+        .zip(written)
+        .position(|(a, b)| a != b)
+        .or_else(|| (original.len() != written.len()).then(|| original.len().min(written.len())));
+    match offset {
+        Some(offset) => Err(format!(
+            "first difference at offset {offset:#x} in {} ({} bytes read, {} bytes written)",
+            describe_offset(model, offset),
+            original.len(),
+            written.len()
+        )),
+        None => Ok(()),
+    }
+}
+
+fn find_models(directory: &Path, models: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_models(&path, models)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("rbm"))
+        {
+            models.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Reads and writes back every model under the directory in `JC2_RBM_CORPUS`, such as models
+/// extracted from the game. Skipped when the variable isn't set.
+#[test]
+fn corpus_round_trips() {
+    let Some(directory) = std::env::var_os("JC2_RBM_CORPUS") else {
+        eprintln!("JC2_RBM_CORPUS is not set, skipping");
+        return;
+    };
+
+    let mut models = Vec::new();
+    find_models(Path::new(&directory), &mut models).expect("corpus should be readable");
+    models.sort();
+    assert!(
+        !models.is_empty(),
+        "no .rbm models found under {}",
+        Path::new(&directory).display()
+    );
+
+    let mut failures = Vec::new();
+    for path in &models {
+        let bytes = std::fs::read(path).expect("model should be readable");
+        let model = match RenderBlockModel::read(&mut Cursor::new(&bytes)) {
+            Ok(model) => model,
+            Err(error) => {
+                failures.push(format!("{}: failed to read: {error}", path.display()));
+                continue;
+            }
+        };
+
+        let mut writer = Cursor::new(Vec::new());
+        let result = model
+            .write(&mut writer)
+            .map_err(|error| format!("failed to write: {error}"))
+            .and_then(|_| compare(&model, &bytes, writer.get_ref()));
+        if let Err(message) = result {
+            failures.push(format!("{}: {message}", path.display()));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} models did not round trip:\n{}",
+        failures.len(),
+        models.len(),
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,298 @@
+use std::{io::Cursor, path::Path};
+
+use jc2_file_formats::archive::{
+    StreamArchive, StreamArchiveBuffer, StreamArchiveCompression, StreamArchiveEndian,
+    StreamArchiveEntry, StreamArchiveIndex, StreamArchiveLayout,
+};
+
+/// Builds an archive by hand, placing each entry's data after `padding` zero bytes following the
+/// previous entry, the way an external tool might have laid it out.
+fn handmade_archive(
+    big_endian: bool,
+    table_padding: usize,
+    entries: &[(&str, &[u8], usize)],
+) -> Vec<u8> {
+    let u32_bytes = |value: usize| {
+        let value = value as u32;
+        if big_endian {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        }
+    };
+
+    let table_used: usize = entries.iter().map(|(name, _, _)| 12 + name.len()).sum();
+    let table_size = table_used + table_padding;
+
+    let mut result = Vec::new();
+    result.extend_from_slice(if big_endian {
+        b"\x00\x00\x00\x04SARC"
+    } else {
+        b"\x04\x00\x00\x00SARC"
+    });
+    result.extend_from_slice(&u32_bytes(2));
+    result.extend_from_slice(&u32_bytes(table_size));
+
+    let mut offset = 16 + table_size;
+    for (name, data, padding) in entries {
+        result.extend_from_slice(&u32_bytes(name.len()));
+        result.extend_from_slice(name.as_bytes());
+        result.extend_from_slice(&u32_bytes(offset));
+        result.extend_from_slice(&u32_bytes(data.len()));
+        offset += data.len() + padding;
+    }
+    result.resize(result.len() + table_padding, 0);
+
+    for (_, data, padding) in entries {
+        result.extend_from_slice(data);
+        result.resize(result.len() + padding, 0);
+    }
+    result
+}
+
+fn round_trip(bytes: &[u8]) -> (StreamArchive, Vec<u8>) {
+    let archive = StreamArchive::read(&mut Cursor::new(bytes)).expect("archive should read");
+    let mut written = Cursor::new(Vec::new());
+    archive.write(&mut written).expect("archive should write");
+    (archive, written.into_inner())
+}
+
+#[test]
+fn written_archive_round_trips() {
+    let mut archive = StreamArchive::default();
+    archive.insert("zebra.rbm", vec![1u8; 5]);
+    archive.insert("apple.dds", vec![2u8; 16]);
+    archive.insert("mango.bin", Vec::new());
+
+    let mut original = Cursor::new(Vec::new());
+    archive.write(&mut original).expect("archive should write");
+    let original = original.into_inner();
+
+    let (read, written) = round_trip(&original);
+    assert_eq!(written, original);
+
+    let names: Vec<_> = read
+        .entries
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect();
+    assert_eq!(names, ["zebra.rbm", "apple.dds", "mango.bin"]);
+}
+
+#[test]
+fn irregular_padding_round_trips() {
+    let entries: &[(&str, &[u8], usize)] = &[
+        ("b.bin", &[1, 2, 3], 13),
+        ("a.bin", &[4; 20], 12),
+        ("c.bin", &[5; 7], 0),
+    ];
+    for big_endian in [false, true] {
+        let original = handmade_archive(big_endian, 36, entries);
+        let (archive, written) = round_trip(&original);
+        assert_eq!(written, original);
+
+        let expected_endian = if big_endian {
+            StreamArchiveEndian::Big
+        } else {
+            StreamArchiveEndian::Little
+        };
+        assert_eq!(archive.endian, expected_endian);
+        assert_eq!(archive.table_padding, Some(36));
+        assert_eq!(
+            archive.entries[0],
+            StreamArchiveEntry {
+                name: "b.bin".into(),
+                data: vec![1, 2, 3],
+                padding: Some(13),
+            }
+        );
+    }
+}
+
+#[test]
+fn replacing_data_keeps_order() {
+    let original = handmade_archive(false, 0, &[("a.bin", &[1; 4], 0), ("b.bin", &[2; 4], 0)]);
+    let (mut archive, _) = round_trip(&original);
+
+    assert_eq!(archive.insert("a.bin", vec![3; 6]), Some(vec![1; 4]));
+    let mut written = Cursor::new(Vec::new());
+    archive.write(&mut written).expect("archive should write");
+
+    let read =
+        StreamArchive::read(&mut Cursor::new(written.into_inner())).expect("archive should read");
+    let names: Vec<_> = read
+        .entries
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect();
+    assert_eq!(names, ["a.bin", "b.bin"]);
+    assert_eq!(read.entries[0].data, [3; 6]);
+    assert_eq!(read.entries[0].padding, Some(2));
+    assert_eq!(read.entries[1].data, [2; 4]);
+}
+
+#[test]
+fn compressed_archive_reads_back() {
+    let mut archive = StreamArchive::default();
+    archive.insert("a.bin", vec![7u8; 4096]);
+
+    let compression = StreamArchiveCompression::from_path(Path::new("test.eez")).with_level(9);
+    assert_eq!(compression, StreamArchiveCompression::Zlib(9));
+    assert_eq!(
+        StreamArchiveCompression::from_path(Path::new("test.ee")),
+        StreamArchiveCompression::None
+    );
+    assert_eq!(
+        StreamArchiveCompression::from_path(Path::new("TEST.EEZ")),
+        StreamArchiveCompression::Zlib(StreamArchiveCompression::DEFAULT_LEVEL)
+    );
+
+    let mut compressed = Vec::new();
+    archive
+        .write_compressed(&mut compressed, compression)
+        .expect("archive should write");
+    assert_eq!(compressed[0], 0x78);
+    assert!(compressed.len() < 4096);
+
+    // Levels built directly are clamped when writing
+    let mut clamped = Vec::new();
+    archive
+        .write_compressed(&mut clamped, StreamArchiveCompression::Zlib(100))
+        .expect("archive should write");
+    assert_eq!(clamped, compressed);
+
+    let read = StreamArchive::read(&mut Cursor::new(compressed)).expect("archive should read");
+    assert_eq!(read.entries.len(), 1);
+    assert_eq!(read.entries[0].data, archive.entries[0].data);
+}
+
+#[test]
+fn console_archive_converts_to_pc() {
+    let entries: &[(&str, &[u8], usize)] = &[("a.bin", &[1, 2, 3], 1), ("b.bin", &[4; 8], 0)];
+    let console = handmade_archive(true, 16, entries);
+    let pc = handmade_archive(false, 16, entries);
+
+    assert_eq!(
+        StreamArchiveEndian::detect(&mut Cursor::new(&console)).expect("magic should read"),
+        StreamArchiveEndian::Big
+    );
+    assert!(
+        StreamArchive::read_endian(&mut Cursor::new(&console), StreamArchiveEndian::Little)
+            .is_err()
+    );
+
+    let archive = StreamArchive::read_endian(&mut Cursor::new(&console), StreamArchiveEndian::Big)
+        .expect("archive should read");
+    let mut written = Cursor::new(Vec::new());
+    archive
+        .with_endian(StreamArchiveEndian::Little)
+        .write(&mut written)
+        .expect("archive should write");
+    assert_eq!(written.into_inner(), pc);
+}
+
+#[test]
+fn dedup_shares_identical_data() {
+    let mut archive = StreamArchive::default();
+    archive.insert("a.bin", vec![1u8; 100]);
+    archive.insert("b.dds", vec![2u8; 30]);
+    archive.insert("c.bin", vec![1u8; 100]);
+
+    let layout = StreamArchiveLayout::new()
+        .with_dedup(true)
+        .with_alignment("DDS", 64);
+    let mut plain = Cursor::new(Vec::new());
+    archive.write(&mut plain).expect("archive should write");
+    let mut packed = Cursor::new(Vec::new());
+    archive
+        .write_with_layout(&mut packed, &layout)
+        .expect("archive should write");
+    assert!(packed.get_ref().len() < plain.get_ref().len());
+
+    packed.set_position(0);
+    let index = StreamArchiveIndex::read(&mut packed).expect("index should read");
+    let offset = |name: &str| index.find(name).expect("entry should exist").offset;
+    assert_eq!(offset("a.bin"), offset("c.bin"));
+    assert_eq!(offset("b.dds") % 64, 0);
+    assert_eq!(index.verify(packed.get_ref().len() as u64), []);
+
+    packed.set_position(0);
+    let read = StreamArchive::read(&mut packed).expect("archive should read");
+    for (read, original) in read.entries.iter().zip(&archive.entries) {
+        assert_eq!((&read.name, &read.data), (&original.name, &original.data));
+    }
+}
+
+#[test]
+fn index_reads_without_data() {
+    let entries: &[(&str, &[u8], usize)] = &[("a.bin", &[1, 2, 3], 1), ("b.bin", &[4; 8], 0)];
+    let bytes = handmade_archive(false, 20, entries);
+    let mut reader = Cursor::new(&bytes);
+    let index = StreamArchiveIndex::read(&mut reader).expect("index should read");
+    assert_eq!(index.table_size, 2 * 17 + 20);
+    assert_eq!(reader.position(), 16 + u64::from(index.table_size));
+
+    let entry = index.find("b.bin").expect("entry should exist");
+    assert_eq!((entry.offset, entry.size), (16 + 54 + 4, 8));
+    assert_eq!(
+        index
+            .extract(&mut reader, entry)
+            .expect("entry should extract"),
+        [4; 8]
+    );
+    assert!(index.find("c.bin").is_none());
+
+    // Entries past the end of the archive fail to extract rather than read short
+    let mut entry = entry.clone();
+    entry.size += 1;
+    assert!(index.extract(&mut reader, &entry).is_err());
+}
+
+#[test]
+fn truncated_tables_are_rejected() {
+    let bytes = handmade_archive(false, 0, &[("a.bin", &[1, 2, 3], 0), ("b.bin", &[4; 8], 0)]);
+
+    // Cut inside the second entry of the table
+    let truncated = &bytes[..16 + 17 + 6];
+    assert!(StreamArchiveIndex::read(&mut Cursor::new(truncated)).is_err());
+    assert!(StreamArchiveBuffer::new(truncated.to_vec()).is_err());
+
+    // A table size smaller than its entries makes the last entry run past the table
+    let mut short = bytes.clone();
+    short[12..16].copy_from_slice(&33u32.to_le_bytes());
+    assert!(StreamArchiveIndex::read(&mut Cursor::new(&short)).is_err());
+    assert!(StreamArchiveBuffer::new(short).is_err());
+}
+
+#[test]
+fn buffer_slices_entries() {
+    let entries: &[(&str, &[u8], usize)] = &[("a.bin", &[1, 2, 3], 1), ("b.bin", &[4; 8], 0)];
+    let bytes = handmade_archive(false, 0, entries);
+    let buffer = StreamArchiveBuffer::new(bytes.clone()).expect("archive should read");
+
+    // The last entry ends right at the end of the buffer
+    let slice = buffer.get("b.bin").expect("entry should exist");
+    assert_eq!(&*slice, &[4; 8]);
+    assert!(buffer.get("c.bin").is_none());
+    let slices: Vec<_> = buffer
+        .iter()
+        .map(|(name, slice)| (name, slice.to_vec()))
+        .collect();
+    assert_eq!(slices, [("a.bin", vec![1, 2, 3]), ("b.bin", vec![4; 8])]);
+
+    // Slices keep the buffer alive
+    drop(buffer);
+    assert_eq!(&*slice, &[4; 8]);
+
+    // Entries reaching past the end of the buffer are refused up front
+    for (offset, size) in [(bytes.len() as u32 - 7, 8), (u32::MAX, 1), (0, u32::MAX)] {
+        let mut out_of_range = bytes.clone();
+        let entry = 16 + 17 + 9;
+        out_of_range[entry..entry + 4].copy_from_slice(&offset.to_le_bytes());
+        out_of_range[entry + 4..entry + 8].copy_from_slice(&size.to_le_bytes());
+        assert!(
+            StreamArchiveBuffer::new(out_of_range).is_err(),
+            "offset {offset:#x} and size {size:#x}"
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use jc2_hashing::{hash_little32, hash_little32_batch};
+
+fn names() -> Vec<String> {
+    (0..100_000)
+        .map(|i| format!("models/vehicles/car_{i:07}_lod{}.rbm", i % 4))
+        .collect()
+}
+
+fn little32(c: &mut Criterion) {
+    let names = names();
+    let mut group = c.benchmark_group("little32");
+    group.throughput(Throughput::Elements(names.len() as u64));
+    group.bench_function("scalar", |b| {
+        b.iter(|| {
+            for name in &names {
+                black_box(hash_little32(black_box(name.as_bytes())));
+            }
+        });
+    });
+    group.bench_function("batch", |b| {
+        b.iter(|| {
+            for chunk in names.chunks(1024) {
+                black_box(hash_little32_batch(black_box(chunk)));
+            }
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, little32);
+criterion_main!(benches);
@@ -0,0 +1,71 @@
+use std::{fmt::Debug, hash::Hash};
+
+use crate::{little32, HashString, HashString64};
+
+/// A hash function used by the engine to turn names into identifiers.
+pub trait HashAlgorithm {
+    type Output: Copy + Debug + Eq + Ord + Hash;
+
+    fn hash(data: &[u8]) -> Self::Output;
+
+    #[inline]
+    fn hash_str(str: &str) -> Self::Output {
+        Self::hash(str.as_bytes())
+    }
+}
+
+/// Bob Jenkins' `hashlittle`, used by [`HashString`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Little32;
+
+impl HashAlgorithm for Little32 {
+    type Output = HashString;
+
+    #[inline]
+    fn hash(data: &[u8]) -> Self::Output {
+        HashString::from_bytes(data)
+    }
+}
+
+/// [`Little32`] over ASCII lowercased input, matching how archives hash file names.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Little32CaseFolded;
+
+impl HashAlgorithm for Little32CaseFolded {
+    type Output = HashString;
+
+    #[inline]
+    fn hash(data: &[u8]) -> Self::Output {
+        let hash = little32::Hasher::new_case_folded(data.len())
+            .update(data)
+            .finish()
+            .unwrap_or_else(|| unreachable!("length is known up front"));
+        HashString::new(hash)
+    }
+}
+
+/// [`Little32`] with a non-zero initial seed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Little32Seeded<const SEED: u32>;
+
+impl<const SEED: u32> HashAlgorithm for Little32Seeded<SEED> {
+    type Output = HashString;
+
+    #[inline]
+    fn hash(data: &[u8]) -> Self::Output {
+        HashString::new(little32::hash_with_seed(data, SEED))
+    }
+}
+
+/// Bob Jenkins' `hashlittle2`, used by [`HashString64`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Little64;
+
+impl HashAlgorithm for Little64 {
+    type Output = HashString64;
+
+    #[inline]
+    fn hash(data: &[u8]) -> Self::Output {
+        HashString64::from_bytes(data)
+    }
+}
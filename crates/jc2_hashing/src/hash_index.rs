@@ -0,0 +1,160 @@
+use std::io::BufRead;
+
+use crate::{HashList, HashListError, HashString};
+
+#[derive(Clone, Debug)]
+struct HashIndexEntry {
+    key: String,
+    name: String,
+    hash: HashString,
+}
+
+/// Sorted index over known names, answering prefix, substring and glob queries with the hashes
+/// of every matching name.
+///
+/// Queries ignore ASCII case and treat `/` and `\` as the same separator.
+#[derive(Clone, Debug, Default)]
+pub struct HashIndex(Vec<HashIndexEntry>);
+
+impl HashIndex {
+    #[inline]
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Reads the same sources as [`HashList::read`], including plain `.filelist` files.
+    #[inline]
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, HashListError> {
+        Ok(Self::from(&HashList::read(reader)?))
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn insert(&mut self, hash: HashString, name: impl Into<String>) {
+        let name: String = name.into();
+        let key = normalize(&name);
+        let index = self.0.partition_point(|entry| entry.key < key);
+        let duplicate = self.0[index..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .any(|entry| entry.hash == hash);
+        if !duplicate {
+            self.0.insert(index, HashIndexEntry { key, name, hash });
+        }
+    }
+
+    /// Adds every name from `list`, including alternates.
+    pub fn extend_from_list(&mut self, list: &HashList) {
+        self.0.extend(list.iter_all().map(|(hash, entry)| {
+            let name = match (entry.as_string(), entry.as_path()) {
+                (Some(string), _) => string.clone(),
+                (None, Some(path)) => path.to_string_lossy().into(),
+                (None, None) => String::new(),
+            };
+            HashIndexEntry {
+                key: normalize(&name),
+                name,
+                hash: *hash,
+            }
+        }));
+        self.0
+            .sort_by(|a, b| a.key.cmp(&b.key).then(a.hash.cmp(&b.hash)));
+        self.0.dedup_by(|a, b| a.key == b.key && a.hash == b.hash);
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (HashString, &str)> {
+        self.0.iter().map(|entry| (entry.hash, entry.name.as_str()))
+    }
+
+    /// Names starting with `prefix`, found by binary search.
+    pub fn find_prefix(&self, prefix: &str) -> impl Iterator<Item = (HashString, &str)> {
+        let prefix = normalize(prefix);
+        let start = self
+            .0
+            .partition_point(|entry| entry.key.as_str() < prefix.as_str());
+        self.0[start..]
+            .iter()
+            .take_while(move |entry| entry.key.starts_with(&prefix))
+            .map(|entry| (entry.hash, entry.name.as_str()))
+    }
+
+    pub fn find_substring(&self, substring: &str) -> impl Iterator<Item = (HashString, &str)> {
+        let substring = normalize(substring);
+        self.0
+            .iter()
+            .filter(move |entry| entry.key.contains(&substring))
+            .map(|entry| (entry.hash, entry.name.as_str()))
+    }
+
+    /// Names matching `pattern`, where `*` matches any run of characters (separators included)
+    /// and `?` matches exactly one, e.g. `*_lod1.rbm`.
+    pub fn find_glob(&self, pattern: &str) -> impl Iterator<Item = (HashString, &str)> {
+        let pattern = normalize(pattern);
+
+        // Any literal prefix narrows the search before wildcards are matched
+        let literal = pattern
+            .find(['*', '?'])
+            .map_or(pattern.as_str(), |i| &pattern[..i]);
+        let start = self.0.partition_point(|entry| entry.key.as_str() < literal);
+        let end = start
+            + self.0[start..]
+                .iter()
+                .take_while(|entry| entry.key.starts_with(literal))
+                .count();
+
+        self.0[start..end]
+            .iter()
+            .filter(move |entry| glob_match(pattern.as_bytes(), entry.key.as_bytes()))
+            .map(|entry| (entry.hash, entry.name.as_str()))
+    }
+}
+
+impl From<&HashList> for HashIndex {
+    #[inline]
+    fn from(list: &HashList) -> Self {
+        let mut result = Self::new();
+        result.extend_from_list(list);
+        result
+    }
+}
+
+#[inline]
+fn normalize(name: &str) -> String {
+    name.to_ascii_lowercase().replace('\\', "/")
+}
+
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => {
+                // Let the last `*` swallow one more character, or fail if there is none
+                let Some((star, matched)) = backtrack else {
+                    return false;
+                };
+                backtrack = Some((star, matched + 1));
+                p = star + 1;
+                t = matched + 1;
+            }
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
@@ -1,8 +1,12 @@
 use std::{
     collections::HashMap,
+    io::{BufRead, Write},
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
+use thiserror::Error;
+
 use crate::HashString;
 
 type Iter<'a> = std::collections::hash_map::Iter<'a, HashString, HashEntry>;
@@ -13,7 +17,20 @@ type IntoKeys = std::collections::hash_map::IntoKeys<HashString, HashEntry>;
 type IntoValues = std::collections::hash_map::IntoValues<HashString, HashEntry>;
 type IntoIter = std::collections::hash_map::IntoIter<HashString, HashEntry>;
 
-#[derive(Debug, Clone)]
+#[derive(Error, Debug)]
+pub enum HashListError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid hash on line {line}: {value:?}")]
+    InvalidHash { line: usize, value: String },
+    #[error("invalid field on line {line}: {value:?}")]
+    InvalidField { line: usize, value: String },
+    #[error("name can not be written: {name:?}")]
+    InvalidName { name: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum HashEntry {
     String(String),
     Path(PathBuf),
@@ -51,6 +68,14 @@ impl HashEntry {
             HashEntry::String(_) => None,
         }
     }
+
+    #[inline]
+    fn name(&self) -> std::borrow::Cow<'_, str> {
+        match self {
+            HashEntry::String(str) => str.into(),
+            HashEntry::Path(path) => path.to_string_lossy(),
+        }
+    }
 }
 
 impl From<HashEntry> for Option<String> {
@@ -81,53 +106,163 @@ impl From<PathBuf> for HashEntry {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum HashConfidence {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl HashConfidence {
+    #[inline]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            HashConfidence::Low => "low",
+            HashConfidence::Medium => "medium",
+            HashConfidence::High => "high",
+        }
+    }
+}
+
+impl FromStr for HashConfidence {
+    type Err = ();
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "low" => Ok(HashConfidence::Low),
+            "medium" => Ok(HashConfidence::Medium),
+            "high" => Ok(HashConfidence::High),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HashMetadata {
+    pub source: Option<String>,
+    pub confidence: HashConfidence,
+    pub alternates: Vec<HashEntry>,
+}
+
+impl HashMetadata {
+    #[inline]
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    #[inline]
+    fn push_alternate(&mut self, entry: HashEntry) {
+        if !self.alternates.contains(&entry) {
+            self.alternates.push(entry);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HashCollisions {
+    /// Inserting a name for a known hash replaces the previous name
+    #[default]
+    Replace,
+    /// Inserting a name for a known hash keeps the previous name, storing the new one as an
+    /// alternate
+    Keep,
+}
+
 #[derive(Default, Debug, Clone)]
-pub struct HashList(HashMap<HashString, HashEntry>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HashList {
+    entries: HashMap<HashString, HashEntry>,
+    metadata: HashMap<HashString, HashMetadata>,
+    collisions: HashCollisions,
+}
 
 impl HashList {
     #[inline]
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            entries: HashMap::new(),
+            metadata: HashMap::new(),
+            collisions: HashCollisions::Replace,
+        }
     }
 
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
-        Self(HashMap::with_capacity(capacity))
+        Self {
+            entries: HashMap::with_capacity(capacity),
+            metadata: HashMap::new(),
+            collisions: HashCollisions::Replace,
+        }
+    }
+
+    #[inline]
+    pub fn with_collisions(mut self, collisions: HashCollisions) -> Self {
+        self.collisions = collisions;
+        self
+    }
+
+    #[inline]
+    pub fn collisions(&self) -> HashCollisions {
+        self.collisions
     }
 
+    /// Inserts every entry under its given hash, handling collisions like [`HashList::insert`].
     #[inline]
     pub fn extend<T: IntoIterator<Item = (HashString, HashEntry)>>(&mut self, iter: T) {
-        self.0.extend(iter);
+        for (hash, entry) in iter {
+            self.insert_hashed(hash, entry);
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
     }
 
     #[inline]
     pub fn keys(&self) -> Keys {
-        self.0.keys()
+        self.entries.keys()
     }
 
     #[inline]
     pub fn values(&self) -> Values {
-        self.0.values()
+        self.entries.values()
     }
 
     #[inline]
     pub fn into_keys(self) -> IntoKeys {
-        self.0.into_keys()
+        self.entries.into_keys()
     }
 
     #[inline]
     pub fn into_values(self) -> IntoValues {
-        self.0.into_values()
+        self.entries.into_values()
     }
 
     #[inline]
     pub fn iter(&self) -> Iter {
-        self.0.iter()
+        self.entries.iter()
     }
 
+    /// Iterates the primary names only; alternates kept by [`HashCollisions::Keep`] are changed
+    /// through [`HashList::metadata_mut`].
     #[inline]
     pub fn iter_mut(&mut self) -> IterMut {
-        self.0.iter_mut()
+        self.entries.iter_mut()
     }
 
     #[inline]
@@ -138,50 +273,273 @@ impl HashList {
         }
     }
 
+    /// Returns the replaced entry; with [`HashCollisions::Keep`] nothing is ever replaced, so
+    /// this always returns `None`.
     #[inline]
     pub fn insert_string(&mut self, string: impl Into<String>) -> Option<HashEntry> {
         let string: String = string.into();
-        self.0.insert(HashString::from_str(&string), string.into())
+        self.insert_hashed(HashString::from_str(&string), string.into())
     }
 
+    /// Returns the replaced entry; with [`HashCollisions::Keep`] nothing is ever replaced, so
+    /// this always returns `None`.
     #[inline]
     pub fn insert_path(&mut self, path: impl Into<PathBuf>) -> Option<HashEntry> {
         let path: PathBuf = path.into();
         if let Some(hash) = HashString::from_path(&path) {
-            self.0.insert(hash, path.into())
+            self.insert_hashed(hash, path.into())
         } else {
             None
         }
     }
 
+    #[inline]
+    fn insert_hashed(&mut self, hash: HashString, entry: HashEntry) -> Option<HashEntry> {
+        match self.collisions {
+            HashCollisions::Replace => self.entries.insert(hash, entry),
+            HashCollisions::Keep => {
+                self.insert_record(hash, entry, None, None);
+                None
+            }
+        }
+    }
+
     #[inline]
     pub fn contains(&self, hash: HashString) -> bool {
-        self.0.contains_key(&hash)
+        self.entries.contains_key(&hash)
     }
 
     #[inline]
     pub fn find(&self, hash: HashString) -> Option<&HashEntry> {
-        self.0.get(&hash)
+        self.entries.get(&hash)
     }
 
     #[inline]
     pub fn find_string(&self, hash: HashString) -> Option<&String> {
-        self.0.get(&hash).and_then(|v| v.as_string())
+        self.entries.get(&hash).and_then(|v| v.as_string())
     }
 
     #[inline]
     pub fn find_path(&self, hash: HashString) -> Option<&Path> {
-        self.0.get(&hash).and_then(|v| v.as_path())
+        self.entries.get(&hash).and_then(|v| v.as_path())
+    }
+
+    /// Every name known for `hash`, starting with the primary entry.
+    #[inline]
+    pub fn find_all(&self, hash: HashString) -> impl Iterator<Item = &HashEntry> {
+        self.entries.get(&hash).into_iter().chain(
+            self.metadata
+                .get(&hash)
+                .into_iter()
+                .flat_map(|metadata| metadata.alternates.iter()),
+        )
+    }
+
+    #[inline]
+    pub fn find_all_paths(&self, hash: HashString) -> impl Iterator<Item = &Path> {
+        self.find_all(hash).filter_map(|v| v.as_path())
+    }
+
+    /// Iterates every name in the list, including alternates.
+    #[inline]
+    pub fn iter_all(&self) -> impl Iterator<Item = (&HashString, &HashEntry)> {
+        self.entries
+            .keys()
+            .flat_map(|hash| self.find_all(*hash).map(move |entry| (hash, entry)))
     }
 
     #[inline]
     pub fn remove(&mut self, hash: HashString) -> Option<HashEntry> {
-        self.0.remove(&hash)
+        self.metadata.remove(&hash);
+        self.entries.remove(&hash)
     }
 
     #[inline]
     pub fn clear(&mut self) {
-        self.0.clear();
+        self.entries.clear();
+        self.metadata.clear();
+    }
+
+    #[inline]
+    pub fn metadata(&self, hash: HashString) -> Option<&HashMetadata> {
+        self.metadata.get(&hash)
+    }
+
+    #[inline]
+    pub fn metadata_mut(&mut self, hash: HashString) -> Option<&mut HashMetadata> {
+        if self.entries.contains_key(&hash) {
+            Some(self.metadata.entry(hash).or_default())
+        } else {
+            None
+        }
+    }
+
+    /// Merges `other` into this list without discarding any names.
+    ///
+    /// When both lists name the same hash differently, the name with the higher confidence
+    /// becomes the primary entry (ties favor `self`) along with its source, and the other is kept
+    /// as an alternate.
+    pub fn merge(&mut self, other: HashList) {
+        let HashList {
+            entries,
+            mut metadata,
+            ..
+        } = other;
+        for (hash, entry) in entries {
+            let theirs = metadata.remove(&hash).unwrap_or_default();
+            let Some(current) = self.entries.get_mut(&hash) else {
+                self.entries.insert(hash, entry);
+                if !theirs.is_default() {
+                    self.metadata.insert(hash, theirs);
+                }
+                continue;
+            };
+
+            // The source and confidence describe the primary name, so they only follow it
+            let ours = self.metadata.entry(hash).or_default();
+            if *current == entry {
+                ours.source = ours.source.take().or(theirs.source);
+                ours.confidence = ours.confidence.max(theirs.confidence);
+            } else if theirs.confidence > ours.confidence {
+                let previous = std::mem::replace(current, entry);
+                ours.push_alternate(previous);
+                ours.source = theirs.source;
+                ours.confidence = theirs.confidence;
+            } else {
+                ours.push_alternate(entry);
+            }
+            for alternate in theirs.alternates {
+                if alternate != *current {
+                    ours.push_alternate(alternate);
+                }
+            }
+            ours.alternates.retain(|alternate| *alternate != *current);
+            if ours.is_default() {
+                self.metadata.remove(&hash);
+            }
+        }
+    }
+
+    /// Reads a hash list, accepting both plain `.filelist` lines and the tab separated format
+    /// produced by [`HashList::write`]; hashes that appear more than once keep every name.
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, HashListError> {
+        let mut result = Self::new().with_collisions(HashCollisions::Keep);
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // Plain file lists only contain paths, so we hash them ourselves. They use `\` as a
+            // separator, which is only understood by `Path` on Windows
+            let Some((hash, fields)) = line.split_once('\t') else {
+                let file_name = line.rsplit(['/', '\\']).next().unwrap_or(line);
+                if let Some(hash) = HashString::from_path(&file_name) {
+                    result.insert_record(hash, PathBuf::from(line).into(), None, None);
+                }
+                continue;
+            };
+
+            let Ok(hash) = u32::from_str_radix(hash, 16).map(HashString::new) else {
+                return Err(HashListError::InvalidHash {
+                    line: index + 1,
+                    value: hash.into(),
+                });
+            };
+            let mut fields = fields.split('\t');
+            let name = fields.next().unwrap_or_default();
+            let mut is_path = false;
+            let mut source = None;
+            let mut confidence = None;
+            for field in fields {
+                match field.split_once('=') {
+                    None if field == "path" => is_path = true,
+                    None if field == "alternate" => {}
+                    Some(("source", value)) => source = Some(value.into()),
+                    Some(("confidence", value)) => {
+                        confidence =
+                            Some(value.parse().map_err(|()| HashListError::InvalidField {
+                                line: index + 1,
+                                value: field.into(),
+                            })?);
+                    }
+                    _ => {
+                        return Err(HashListError::InvalidField {
+                            line: index + 1,
+                            value: field.into(),
+                        })
+                    }
+                }
+            }
+
+            let entry = if is_path {
+                HashEntry::Path(name.into())
+            } else {
+                HashEntry::String(name.into())
+            };
+            result.insert_record(hash, entry, source, confidence);
+        }
+        Ok(result)
+    }
+
+    /// Writes the list sorted by hash, one name per line, so lists can be diffed and merged.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), HashListError> {
+        let mut hashes: Vec<_> = self.entries.keys().copied().collect();
+        hashes.sort_unstable();
+
+        for hash in hashes {
+            let metadata = self.metadata.get(&hash);
+            let entries = std::iter::once((&self.entries[&hash], false)).chain(
+                metadata
+                    .into_iter()
+                    .flat_map(|metadata| metadata.alternates.iter().map(|entry| (entry, true))),
+            );
+            for (entry, is_alternate) in entries {
+                let name = entry.name();
+                if name.contains(['\t', '\n', '\r']) {
+                    return Err(HashListError::InvalidName { name: name.into() });
+                }
+                write!(writer, "{:08x}\t{name}", hash.hash())?;
+                if matches!(entry, HashEntry::Path(_)) {
+                    write!(writer, "\tpath")?;
+                }
+                if is_alternate {
+                    write!(writer, "\talternate")?;
+                } else if let Some(metadata) = metadata {
+                    if let Some(source) = &metadata.source {
+                        write!(writer, "\tsource={source}")?;
+                    }
+                    if metadata.confidence != HashConfidence::default() {
+                        write!(writer, "\tconfidence={}", metadata.confidence.as_str())?;
+                    }
+                }
+                writeln!(writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_record(
+        &mut self,
+        hash: HashString,
+        entry: HashEntry,
+        source: Option<String>,
+        confidence: Option<HashConfidence>,
+    ) {
+        // Alternates without a primary entry are promoted, so no name is ever lost
+        match self.entries.get(&hash) {
+            None => {
+                self.entries.insert(hash, entry);
+            }
+            Some(current) if *current == entry => {}
+            Some(_) => self.metadata.entry(hash).or_default().push_alternate(entry),
+        }
+        if source.is_some() || confidence.is_some() {
+            let metadata = self.metadata.entry(hash).or_default();
+            metadata.source = source.or(metadata.source.take());
+            metadata.confidence = confidence.unwrap_or(metadata.confidence);
+        }
     }
 }
 
@@ -191,7 +549,7 @@ impl IntoIterator for HashList {
 
     #[inline]
     fn into_iter(self) -> IntoIter {
-        self.0.into_iter()
+        self.entries.into_iter()
     }
 }
 
@@ -217,7 +575,11 @@ impl<'a> IntoIterator for &'a mut HashList {
 
 impl FromIterator<(HashString, HashEntry)> for HashList {
     fn from_iter<T: IntoIterator<Item = (HashString, HashEntry)>>(iter: T) -> Self {
-        Self(HashMap::from_iter(iter))
+        Self {
+            entries: HashMap::from_iter(iter),
+            metadata: HashMap::new(),
+            collisions: HashCollisions::Replace,
+        }
     }
 }
 
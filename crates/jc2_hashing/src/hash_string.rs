@@ -2,7 +2,7 @@ use std::path::Path;
 
 use binrw::binrw;
 
-use super::hash_little32;
+use super::{hash_little32, hash_little64, Little32Hasher};
 
 #[binrw]
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -32,10 +32,12 @@ impl HashString {
     #[inline]
     pub fn from_path<P: AsRef<Path>>(path: &P) -> Option<Self> {
         let path = path.as_ref();
-        path.file_name().filter(|a| a.is_ascii()).map(|a| {
-            Self(hash_little32(
-                a.to_ascii_lowercase().to_string_lossy().as_bytes(),
-            ))
+        path.file_name().filter(|a| a.is_ascii()).and_then(|a| {
+            let bytes = a.as_encoded_bytes();
+            Little32Hasher::new_case_folded(bytes.len())
+                .update(bytes)
+                .finish()
+                .map(Self)
         })
     }
 
@@ -78,5 +80,64 @@ impl From<HashString> for u32 {
     }
 }
 
+/// 64-bit sibling of [`HashString`], for formats that hash names with [`hash_little64`].
+#[binrw]
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct HashString64(u64);
+
+impl HashString64 {
+    #[inline]
+    pub const fn new(value: u64) -> Self {
+        Self(value)
+    }
+
+    #[inline]
+    pub const fn from_bytes(bytes: &[u8]) -> Self {
+        Self(hash_little64(bytes))
+    }
+
+    #[inline]
+    pub const fn from_str(str: &str) -> Self {
+        Self(hash_little64(str.as_bytes()))
+    }
+
+    #[inline]
+    pub const fn hash(&self) -> u64 {
+        self.0
+    }
+
+    #[inline]
+    pub fn hash_mut(&mut self) -> &mut u64 {
+        &mut self.0
+    }
+}
+
+impl From<String> for HashString64 {
+    #[inline]
+    fn from(value: String) -> Self {
+        Self::from_bytes(value.as_bytes())
+    }
+}
+
+impl From<u64> for HashString64 {
+    #[inline]
+    fn from(value: u64) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<HashString64> for u64 {
+    #[inline]
+    fn from(value: HashString64) -> Self {
+        value.0
+    }
+}
+
 const_assert_eq!(HashString::from_bytes(b"rico").hash(), 0x6041E481);
 const_assert_eq!(HashString::from_str("jc2").hash(), 0xCDF21378);
+const_assert_eq!(HashString64::from_str("jc2").hash() as u32, 0xCDF21378);
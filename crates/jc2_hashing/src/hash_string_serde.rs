@@ -0,0 +1,119 @@
+use std::{cell::RefCell, fmt, sync::Arc};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{HashList, HashString};
+
+thread_local! {
+    static DICTIONARY: RefCell<Option<Arc<HashList>>> = const { RefCell::new(None) };
+}
+
+/// Restores the previous dictionary, even if serialization panics.
+struct DictionaryGuard(Option<Arc<HashList>>);
+
+impl Drop for DictionaryGuard {
+    #[inline]
+    fn drop(&mut self) {
+        DICTIONARY.with_borrow_mut(|dictionary| *dictionary = self.0.take());
+    }
+}
+
+impl HashString {
+    /// Runs `f` with `dictionary` used to resolve names while serializing or deserializing on the
+    /// current thread.
+    ///
+    /// Human-readable formats write a [`HashString`] as its name when the dictionary knows it, and
+    /// as `0x` prefixed hex otherwise. Without a dictionary, names are read back with
+    /// [`HashString::from_path`] if they contain a separator and [`HashString::from_str`] if not.
+    pub fn with_dictionary<R>(dictionary: impl Into<Arc<HashList>>, f: impl FnOnce() -> R) -> R {
+        let previous = DICTIONARY.with_borrow_mut(|current| current.replace(dictionary.into()));
+        let _guard = DictionaryGuard(previous);
+        f()
+    }
+
+    fn from_name(name: &str) -> Self {
+        // `Path` only splits on `\` on Windows, but names use either separator everywhere
+        let file_name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+        let string = Self::from_str(name);
+        let path = Self::from_path(&file_name).unwrap_or(string);
+        DICTIONARY.with_borrow(|dictionary| match dictionary {
+            Some(dictionary) if dictionary.contains(string) => string,
+            Some(dictionary) if dictionary.contains(path) => path,
+            _ if name.contains(['/', '\\']) => path,
+            _ => string,
+        })
+    }
+}
+
+#[inline]
+fn parse_hex(value: &str) -> Option<u32> {
+    let hex = value.strip_prefix("0x")?;
+    (hex.len() == 8)
+        .then(|| u32::from_str_radix(hex, 16).ok())
+        .flatten()
+}
+
+impl Serialize for HashString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_u32(self.hash());
+        }
+
+        let name = DICTIONARY.with_borrow(|dictionary| {
+            let entry = dictionary.as_ref()?.find(*self)?;
+            let name = match (entry.as_string(), entry.as_path()) {
+                (Some(string), _) => string.clone(),
+                (None, Some(path)) => path.to_str()?.to_owned(),
+                (None, None) => return None,
+            };
+            // A name that reads back as hex would resolve to a different hash
+            parse_hex(&name).is_none().then_some(name)
+        });
+        match name {
+            Some(name) => serializer.serialize_str(&name),
+            None => serializer.collect_str(&format_args!("0x{:08x}", self.hash())),
+        }
+    }
+}
+
+struct HashStringVisitor;
+
+impl de::Visitor<'_> for HashStringVisitor {
+    type Value = HashString;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a name, a 0x prefixed hex hash or an integer hash")
+    }
+
+    #[inline]
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        u32::try_from(value)
+            .map(HashString::new)
+            .or(Err(E::invalid_value(
+                de::Unexpected::Unsigned(value),
+                &self,
+            )))
+    }
+
+    #[inline]
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        u32::try_from(value)
+            .map(HashString::new)
+            .or(Err(E::invalid_value(de::Unexpected::Signed(value), &self)))
+    }
+
+    #[inline]
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(parse_hex(value).map_or_else(|| HashString::from_name(value), HashString::new))
+    }
+}
+
+impl<'de> Deserialize<'de> for HashString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(HashStringVisitor)
+        } else {
+            deserializer.deserialize_u32(HashStringVisitor)
+        }
+    }
+}
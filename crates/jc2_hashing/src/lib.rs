@@ -1,15 +1,34 @@
 #[macro_use]
 extern crate static_assertions;
 
+pub use jc2_hashing_macros::{expand_hashes, hash};
 pub use paste::paste;
 
+mod hash_algorithm;
+pub use hash_algorithm::{HashAlgorithm, Little32, Little32CaseFolded, Little32Seeded, Little64};
+
+mod hash_index;
+pub use hash_index::HashIndex;
+
 mod hash_list;
-pub use hash_list::HashList;
+pub use hash_list::{
+    HashCollisions, HashConfidence, HashEntry, HashList, HashListError, HashMetadata,
+};
 
 mod hash_string_macros;
 
 mod hash_string;
-pub use hash_string::HashString;
+pub use hash_string::{HashString, HashString64};
+
+#[cfg(feature = "serde")]
+mod hash_string_serde;
 
 mod little32;
-pub use little32::hash as hash_little32;
+pub use little32::{
+    hash as hash_little32, hash64 as hash_little64, hash_batch as hash_little32_batch,
+    hash_with_seed as hash_little32_with_seed, Hasher as Little32Hasher,
+    BATCH_LANES as LITTLE32_BATCH_LANES,
+};
+
+mod name_recovery;
+pub use name_recovery::{NameRecovery, NameRecoveryError, NameRecoveryHit, NameTemplate};
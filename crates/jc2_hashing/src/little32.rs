@@ -1,54 +1,46 @@
 const INITIAL_VALUE: u32 = 0xDEADBEEF;
+const BLOCK_SIZE: usize = 12;
 
 #[inline(always)]
 pub const fn hash(data: &[u8]) -> u32 {
+    hash_pair(data, 0, 0).0
+}
+
+/// Same as [`hash`], but with `seed` added to the initial state, as some formats hash with a
+/// non-zero seed.
+#[inline(always)]
+pub const fn hash_with_seed(data: &[u8], seed: u32) -> u32 {
+    hash_pair(data, seed, 0).0
+}
+
+/// 64-bit variant (Jenkins' `hashlittle2`), whose low half equals [`hash`].
+///
+/// Like [`hash`], empty input still goes through the final mix, where `lookup3.c` returns its
+/// initial state unchanged.
+#[inline(always)]
+pub const fn hash64(data: &[u8]) -> u64 {
+    let (c, b) = hash_pair(data, 0, 0);
+    c as u64 | ((b as u64) << 32)
+}
+
+#[inline(always)]
+const fn hash_pair(data: &[u8], primary_seed: u32, secondary_seed: u32) -> (u32, u32) {
     let l = data.len();
-    let mut a = INITIAL_VALUE.wrapping_add(l as u32);
+    let mut a = INITIAL_VALUE
+        .wrapping_add(l as u32)
+        .wrapping_add(primary_seed);
     let mut b = a;
-    let mut c = a;
+    let mut c = a.wrapping_add(secondary_seed);
 
     let mut i = 0;
 
-    while l - i > 12 {
-        a = a.wrapping_add(
-            data[i] as u32
-                | ((data[i + 1] as u32) << 8)
-                | ((data[i + 2] as u32) << 16)
-                | ((data[i + 3] as u32) << 24),
-        );
-        b = b.wrapping_add(
-            data[i + 4] as u32
-                | ((data[i + 5] as u32) << 8)
-                | ((data[i + 6] as u32) << 16)
-                | ((data[i + 7] as u32) << 24),
-        );
-        c = c.wrapping_add(
-            data[i + 8] as u32
-                | ((data[i + 9] as u32) << 8)
-                | ((data[i + 10] as u32) << 16)
-                | ((data[i + 11] as u32) << 24),
-        );
-
-        a = a.wrapping_sub(c);
-        a ^= c.rotate_left(4);
-        c = c.wrapping_add(b);
-        b = b.wrapping_sub(a);
-        b ^= a.rotate_left(6);
-        a = a.wrapping_add(c);
-        c = c.wrapping_sub(b);
-        c ^= b.rotate_left(8);
-        b = b.wrapping_add(a);
-        a = a.wrapping_sub(c);
-        a ^= c.rotate_left(16);
-        c = c.wrapping_add(b);
-        b = b.wrapping_sub(a);
-        b ^= a.rotate_left(19);
-        a = a.wrapping_add(c);
-        c = c.wrapping_sub(b);
-        c ^= b.rotate_left(4);
-        b = b.wrapping_add(a);
-
-        i += 12;
+    while l - i > BLOCK_SIZE {
+        a = a.wrapping_add(read_u32(data, i));
+        b = b.wrapping_add(read_u32(data, i + 4));
+        c = c.wrapping_add(read_u32(data, i + 8));
+        (a, b, c) = mix(a, b, c);
+
+        i += BLOCK_SIZE;
     }
 
     use const_for::const_for;
@@ -67,6 +59,42 @@ pub const fn hash(data: &[u8]) -> u32 {
         }
     });
 
+    finalize(a, b, c)
+}
+
+#[inline(always)]
+const fn read_u32(data: &[u8], i: usize) -> u32 {
+    data[i] as u32
+        | ((data[i + 1] as u32) << 8)
+        | ((data[i + 2] as u32) << 16)
+        | ((data[i + 3] as u32) << 24)
+}
+
+#[inline(always)]
+const fn mix(mut a: u32, mut b: u32, mut c: u32) -> (u32, u32, u32) {
+    a = a.wrapping_sub(c);
+    a ^= c.rotate_left(4);
+    c = c.wrapping_add(b);
+    b = b.wrapping_sub(a);
+    b ^= a.rotate_left(6);
+    a = a.wrapping_add(c);
+    c = c.wrapping_sub(b);
+    c ^= b.rotate_left(8);
+    b = b.wrapping_add(a);
+    a = a.wrapping_sub(c);
+    a ^= c.rotate_left(16);
+    c = c.wrapping_add(b);
+    b = b.wrapping_sub(a);
+    b ^= a.rotate_left(19);
+    a = a.wrapping_add(c);
+    c = c.wrapping_sub(b);
+    c ^= b.rotate_left(4);
+    b = b.wrapping_add(a);
+    (a, b, c)
+}
+
+#[inline(always)]
+const fn finalize(mut a: u32, mut b: u32, mut c: u32) -> (u32, u32) {
     c ^= b;
     c = c.wrapping_sub(b.rotate_left(14));
     a ^= c;
@@ -82,8 +110,171 @@ pub const fn hash(data: &[u8]) -> u32 {
     c ^= b;
     c = c.wrapping_sub(b.rotate_left(24));
 
-    c
+    (c, b)
+}
+
+/// Number of inputs [`hash_batch`] hashes side by side.
+pub const BATCH_LANES: usize = 8;
+
+/// Hashes many inputs at once, returning the same values as calling [`hash`] on each of them.
+///
+/// Inputs of equal length are hashed [`BATCH_LANES`] at a time with lane-wise arithmetic the
+/// compiler can vectorize, while any leftovers fall back to [`hash`].
+pub fn hash_batch<T: AsRef<[u8]>>(inputs: &[T]) -> Vec<u32> {
+    // Counting sort by length, so every lane of a batch runs the same number of rounds
+    let max_length = inputs.iter().map(|i| i.as_ref().len()).max().unwrap_or(0);
+    let mut offsets = vec![0usize; max_length + 2];
+    for input in inputs {
+        offsets[input.as_ref().len() + 1] += 1;
+    }
+    for length in 1..offsets.len() {
+        offsets[length] += offsets[length - 1];
+    }
+    let mut order = vec![0usize; inputs.len()];
+    let mut cursors = offsets.clone();
+    for (index, input) in inputs.iter().enumerate() {
+        let cursor = &mut cursors[input.as_ref().len()];
+        order[*cursor] = index;
+        *cursor += 1;
+    }
+
+    let mut result = vec![0u32; inputs.len()];
+    for (length, range) in offsets.windows(2).enumerate() {
+        let mut chunks = order[range[0]..range[1]].chunks_exact(BATCH_LANES);
+        for chunk in &mut chunks {
+            let lanes = std::array::from_fn(|lane| inputs[chunk[lane]].as_ref());
+            for (&index, hash) in chunk.iter().zip(hash_lanes(&lanes, length)) {
+                result[index] = hash;
+            }
+        }
+        for &index in chunks.remainder() {
+            result[index] = hash(inputs[index].as_ref());
+        }
+    }
+    result
+}
+
+#[inline]
+fn hash_lanes(inputs: &[&[u8]; BATCH_LANES], length: usize) -> [u32; BATCH_LANES] {
+    let mut a = [INITIAL_VALUE.wrapping_add(length as u32); BATCH_LANES];
+    let mut b = a;
+    let mut c = a;
+
+    let mut i = 0;
+    while length - i > BLOCK_SIZE {
+        for lane in 0..BATCH_LANES {
+            let block = &inputs[lane][i..i + BLOCK_SIZE];
+            a[lane] = a[lane].wrapping_add(read_u32(block, 0));
+            b[lane] = b[lane].wrapping_add(read_u32(block, 4));
+            c[lane] = c[lane].wrapping_add(read_u32(block, 8));
+        }
+        for lane in 0..BATCH_LANES {
+            (a[lane], b[lane], c[lane]) = mix(a[lane], b[lane], c[lane]);
+        }
+        i += BLOCK_SIZE;
+    }
+
+    // Zero padding leaves the sums untouched, so the tail can be added as whole words
+    for lane in 0..BATCH_LANES {
+        let mut block = [0u8; BLOCK_SIZE];
+        block[..length - i].copy_from_slice(&inputs[lane][i..length]);
+        a[lane] = a[lane].wrapping_add(read_u32(&block, 0));
+        b[lane] = b[lane].wrapping_add(read_u32(&block, 4));
+        c[lane] = c[lane].wrapping_add(read_u32(&block, 8));
+    }
+
+    std::array::from_fn(|lane| finalize(a[lane], b[lane], c[lane]).0)
+}
+
+/// Incremental version of [`hash`], producing identical results without a contiguous buffer.
+///
+/// The seed depends on the total input length, so it must be known up front; [`Hasher::finish`]
+/// returns `None` if a different number of bytes was written.
+#[derive(Clone, Debug)]
+pub struct Hasher {
+    a: u32,
+    b: u32,
+    c: u32,
+    length: usize,
+    written: usize,
+    block: [u8; BLOCK_SIZE],
+    block_length: usize,
+    fold_case: bool,
+}
+
+impl Hasher {
+    #[inline]
+    pub const fn new(length: usize) -> Self {
+        let seed = INITIAL_VALUE.wrapping_add(length as u32);
+        Self {
+            a: seed,
+            b: seed,
+            c: seed,
+            length,
+            written: 0,
+            block: [0u8; BLOCK_SIZE],
+            block_length: 0,
+            fold_case: false,
+        }
+    }
+
+    /// Lowercases ASCII input as it is written, matching how archives hash file names.
+    #[inline]
+    pub const fn new_case_folded(length: usize) -> Self {
+        let mut result = Self::new(length);
+        result.fold_case = true;
+        result
+    }
+
+    #[inline]
+    pub fn update(&mut self, data: &[u8]) -> &mut Self {
+        for &byte in data {
+            // A full block is only mixed once we know it is not the final block
+            if self.block_length == BLOCK_SIZE {
+                self.a = self.a.wrapping_add(read_u32(&self.block, 0));
+                self.b = self.b.wrapping_add(read_u32(&self.block, 4));
+                self.c = self.c.wrapping_add(read_u32(&self.block, 8));
+                (self.a, self.b, self.c) = mix(self.a, self.b, self.c);
+                self.block_length = 0;
+            }
+            self.block[self.block_length] = if self.fold_case {
+                byte.to_ascii_lowercase()
+            } else {
+                byte
+            };
+            self.block_length += 1;
+        }
+        self.written += data.len();
+        self
+    }
+
+    #[inline]
+    pub fn finish(&self) -> Option<u32> {
+        if self.written != self.length {
+            return None;
+        }
+
+        let (mut a, mut b, mut c) = (self.a, self.b, self.c);
+        for (o, byte) in self.block[..self.block_length].iter().enumerate() {
+            let value = (*byte as u32) << (o % 4 * 8);
+            match o / 4 {
+                0 => a = a.wrapping_add(value),
+                1 => b = b.wrapping_add(value),
+                _ => c = c.wrapping_add(value),
+            }
+        }
+        Some(finalize(a, b, c).0)
+    }
 }
 
 const_assert_eq!(hash(b"rico"), 0x6041E481);
 const_assert_eq!(hash(b"jc2"), 0xCDF21378);
+const_assert_eq!(hash64(b"jc2") as u32, hash(b"jc2"));
+const_assert_eq!(hash_with_seed(b"jc2", 0), hash(b"jc2"));
+
+// The hash! macro carries its own copy of the algorithm
+const_assert_eq!(hash(b"rico"), jc2_hashing_macros::hash!("rico"));
+const_assert_eq!(
+    hash(b"BillboardFoliageRenderBlock"),
+    jc2_hashing_macros::hash!("BillboardFoliageRenderBlock")
+);
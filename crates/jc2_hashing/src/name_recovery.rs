@@ -0,0 +1,318 @@
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
+    ops::Range,
+    str::FromStr,
+};
+
+use thiserror::Error;
+
+use crate::{hash_little32_batch, HashList, HashString};
+
+const BATCH_SIZE: usize = 1024;
+
+#[derive(Error, Debug)]
+pub enum NameRecoveryError {
+    #[error("unterminated placeholder in template: {template:?}")]
+    UnterminatedPlaceholder { template: String },
+    #[error("unknown placeholder {placeholder:?} in template: {template:?}")]
+    UnknownPlaceholder {
+        template: String,
+        placeholder: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TemplateSegment {
+    Literal(String),
+    Placeholder(String),
+}
+
+/// A candidate name pattern such as `{prefix}_{n}.rbm`, where every `{name}` is substituted with
+/// each word of the vocabulary registered under that name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameTemplate {
+    source: String,
+    segments: Vec<TemplateSegment>,
+}
+
+impl NameTemplate {
+    pub fn parse(template: impl Into<String>) -> Result<Self, NameRecoveryError> {
+        let source: String = template.into();
+        let mut segments = Vec::new();
+        let mut remaining = source.as_str();
+        while let Some(start) = remaining.find('{') {
+            let Some(end) = remaining[start..].find('}').map(|end| start + end) else {
+                return Err(NameRecoveryError::UnterminatedPlaceholder { template: source });
+            };
+            if start > 0 {
+                segments.push(TemplateSegment::Literal(
+                    remaining[..start].to_ascii_lowercase(),
+                ));
+            }
+            segments.push(TemplateSegment::Placeholder(
+                remaining[start + 1..end].into(),
+            ));
+            remaining = &remaining[end + 1..];
+        }
+        if !remaining.is_empty() {
+            segments.push(TemplateSegment::Literal(remaining.to_ascii_lowercase()));
+        }
+        Ok(Self { source, segments })
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    #[inline]
+    pub fn placeholders(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().filter_map(|segment| match segment {
+            TemplateSegment::Placeholder(name) => Some(name.as_str()),
+            TemplateSegment::Literal(_) => None,
+        })
+    }
+
+    #[inline]
+    fn build(&self, vocabularies: &[&[String]], mut index: u64, name: &mut String) {
+        name.clear();
+        let mut vocabularies = vocabularies.iter();
+        for segment in &self.segments {
+            match segment {
+                TemplateSegment::Literal(literal) => name.push_str(literal),
+                TemplateSegment::Placeholder(_) => {
+                    let Some(words) = vocabularies.next() else {
+                        unreachable!("template placeholders were validated");
+                    };
+                    let radix = words.len() as u64;
+                    name.push_str(&words[(index % radix) as usize]);
+                    index /= radix;
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for NameTemplate {
+    type Err = NameRecoveryError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameRecoveryHit {
+    pub hash: HashString,
+    pub name: String,
+}
+
+/// Resolves unknown hashes by generating candidate names from vocabularies and templates.
+///
+/// Candidates are lowercased before hashing, matching how archive tables hash their file names.
+#[derive(Clone, Debug)]
+pub struct NameRecovery {
+    targets: HashSet<HashString>,
+    names: Vec<String>,
+    vocabularies: HashMap<String, Vec<String>>,
+    templates: Vec<NameTemplate>,
+    threads: NonZeroUsize,
+}
+
+impl NameRecovery {
+    pub fn new(targets: impl IntoIterator<Item = HashString>) -> Self {
+        Self {
+            targets: targets.into_iter().collect(),
+            names: Vec::new(),
+            vocabularies: HashMap::new(),
+            templates: Vec::new(),
+            threads: std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+        }
+    }
+
+    #[inline]
+    pub fn with_threads(mut self, threads: NonZeroUsize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Adds complete names to test as-is, such as the lines of a wordlist.
+    pub fn with_names<T: AsRef<str>>(mut self, names: impl IntoIterator<Item = T>) -> Self {
+        self.names.extend(
+            names
+                .into_iter()
+                .map(|name| name.as_ref().to_ascii_lowercase()),
+        );
+        self
+    }
+
+    /// Adds words to the vocabulary substituted for `{name}` in templates.
+    pub fn with_vocabulary<T: AsRef<str>>(
+        mut self,
+        name: impl Into<String>,
+        words: impl IntoIterator<Item = T>,
+    ) -> Self {
+        let vocabulary = self.vocabularies.entry(name.into()).or_default();
+        let mut known: HashSet<String> = vocabulary.iter().cloned().collect();
+        for word in words {
+            let word = word.as_ref().to_ascii_lowercase();
+            if known.insert(word.clone()) {
+                vocabulary.push(word);
+            }
+        }
+        self
+    }
+
+    /// Adds the numbers in `range` to the vocabulary `{name}`, zero-padded to `width` digits.
+    pub fn with_range(self, name: impl Into<String>, range: Range<u32>, width: usize) -> Self {
+        self.with_vocabulary(name, range.map(|n| format!("{n:0width$}")))
+    }
+
+    /// Splits known paths into the `{dir}`, `{stem}` and `{ext}` vocabularies, so templates can
+    /// recombine patterns already seen in the game data. Both `/` and `\` separate directories,
+    /// whatever the platform.
+    pub fn with_known_names(self, list: &HashList) -> Self {
+        let mut directories = Vec::new();
+        let mut stems = Vec::new();
+        let mut extensions = Vec::new();
+        for entry in list.values() {
+            let path = match (entry.as_path(), entry.as_string()) {
+                (Some(path), _) => path.to_str(),
+                (None, Some(string)) => Some(string.as_str()),
+                (None, None) => None,
+            };
+            let Some(path) = path else {
+                continue;
+            };
+            let file_name = match path.rsplit_once(['/', '\\']) {
+                Some((directory, file_name)) => {
+                    directories.push(directory.to_owned());
+                    file_name
+                }
+                None => path,
+            };
+            match file_name.rsplit_once('.') {
+                Some((stem, extension)) if !stem.is_empty() => {
+                    stems.push(stem.to_owned());
+                    extensions.push(extension.to_owned());
+                }
+                _ => stems.push(file_name.to_owned()),
+            }
+        }
+        self.with_vocabulary("dir", directories)
+            .with_vocabulary("stem", stems)
+            .with_vocabulary("ext", extensions)
+    }
+
+    #[inline]
+    pub fn with_template(mut self, template: NameTemplate) -> Self {
+        self.templates.push(template);
+        self
+    }
+
+    /// Total number of candidates that [`NameRecovery::run`] will hash.
+    pub fn candidate_count(&self) -> u64 {
+        self.templates
+            .iter()
+            .map(|template| {
+                template.placeholders().fold(1u64, |count, placeholder| {
+                    let words = self.vocabularies.get(placeholder).map_or(0, Vec::len);
+                    count.saturating_mul(words as u64)
+                })
+            })
+            .fold(self.names.len() as u64, u64::saturating_add)
+    }
+
+    /// Hashes every candidate across the configured number of threads, calling `on_hit` for each
+    /// candidate that matches a target and returning every resolved name.
+    pub fn run<F>(&self, on_hit: F) -> Result<HashList, NameRecoveryError>
+    where
+        F: Fn(&NameRecoveryHit) + Sync,
+    {
+        // Validate templates up front, so we never fail halfway through a search
+        let mut templates = Vec::with_capacity(self.templates.len());
+        for template in &self.templates {
+            let mut vocabularies = Vec::new();
+            for placeholder in template.placeholders() {
+                let Some(vocabulary) = self.vocabularies.get(placeholder) else {
+                    return Err(NameRecoveryError::UnknownPlaceholder {
+                        template: template.source.clone(),
+                        placeholder: placeholder.into(),
+                    });
+                };
+                vocabularies.push(vocabulary.as_slice());
+            }
+            templates.push((template, vocabularies));
+        }
+
+        let mut hits = Vec::new();
+        let check = |name: &str, hash: HashString, hits: &mut Vec<NameRecoveryHit>| {
+            if self.targets.contains(&hash) {
+                let hit = NameRecoveryHit {
+                    hash,
+                    name: name.into(),
+                };
+                on_hit(&hit);
+                hits.push(hit);
+            }
+        };
+
+        for name in &self.names {
+            check(name, HashString::from_str(name), &mut hits);
+        }
+
+        for (template, vocabularies) in &templates {
+            let count = vocabularies
+                .iter()
+                .try_fold(1u64, |count, words| count.checked_mul(words.len() as u64))
+                .unwrap_or(u64::MAX);
+            if count == 0 {
+                continue;
+            }
+
+            // Each thread walks a contiguous slice of the candidate space
+            let threads = (self.threads.get() as u64).min(count);
+            let chunk = count.div_ceil(threads);
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = (0..threads)
+                    .map(|thread| {
+                        let start = thread * chunk;
+                        let end = (start + chunk).min(count);
+                        scope.spawn(move || {
+                            let mut hits = Vec::new();
+                            let mut names = vec![String::new(); BATCH_SIZE];
+                            let mut index = start;
+                            while index < end {
+                                let count = (end - index).min(BATCH_SIZE as u64) as usize;
+                                let names = &mut names[..count];
+                                for (offset, name) in names.iter_mut().enumerate() {
+                                    template.build(vocabularies, index + offset as u64, name);
+                                }
+                                let hashes = hash_little32_batch(names);
+                                for (name, hash) in names.iter().zip(hashes) {
+                                    check(name, HashString::new(hash), &mut hits);
+                                }
+                                index += count as u64;
+                            }
+                            hits
+                        })
+                    })
+                    .collect();
+                for handle in handles {
+                    match handle.join() {
+                        Ok(thread_hits) => hits.extend(thread_hits),
+                        Err(panic) => std::panic::resume_unwind(panic),
+                    }
+                }
+            });
+        }
+
+        let mut list = HashList::with_capacity(hits.len());
+        for hit in hits {
+            list.insert_string(hit.name);
+        }
+        Ok(list)
+    }
+}
@@ -0,0 +1,102 @@
+use std::io::Cursor;
+
+use jc2_hashing::{HashIndex, HashString};
+
+const NAMES: [&str; 4] = [
+    "Models\\Jeep_LOD1.rbm",
+    "models/jeep_lod2.rbm",
+    "models/arve_lod1.rbm",
+    "textures/jeep_dif.dds",
+];
+
+fn index() -> HashIndex {
+    let mut index = HashIndex::new();
+    for name in NAMES {
+        index.insert(HashString::from_str(name), name);
+    }
+    index
+}
+
+fn names<'a>(found: impl Iterator<Item = (HashString, &'a str)>) -> Vec<&'a str> {
+    let mut result: Vec<_> = found.map(|(_, name)| name).collect();
+    result.sort_unstable();
+    result
+}
+
+#[test]
+fn glob_wildcards_match() {
+    let index = index();
+    assert_eq!(
+        names(index.find_glob("*_lod1.rbm")),
+        ["Models\\Jeep_LOD1.rbm", "models/arve_lod1.rbm"]
+    );
+    assert_eq!(
+        names(index.find_glob("models/jeep_lod?.rbm")),
+        ["Models\\Jeep_LOD1.rbm", "models/jeep_lod2.rbm"]
+    );
+    assert_eq!(
+        names(index.find_glob("?odels/*1*")),
+        ["Models\\Jeep_LOD1.rbm", "models/arve_lod1.rbm"]
+    );
+
+    // `*` crosses separators, `?` stands for exactly one character
+    assert_eq!(names(index.find_glob("*")).len(), NAMES.len());
+    assert_eq!(names(index.find_glob("*jeep*")).len(), 3);
+    assert!(names(index.find_glob("models/jeep_lod??.rbm")).is_empty());
+    assert!(names(index.find_glob("models/jeep_lod.rbm")).is_empty());
+}
+
+#[test]
+fn empty_patterns() {
+    let index = index();
+    assert!(names(index.find_glob("")).is_empty());
+    assert_eq!(names(index.find_prefix("")).len(), NAMES.len());
+    assert_eq!(names(index.find_substring("")).len(), NAMES.len());
+    assert!(names(HashIndex::new().find_glob("*")).is_empty());
+}
+
+#[test]
+fn prefixes_without_match() {
+    let index = index();
+    assert!(names(index.find_prefix("a")).is_empty());
+    assert!(names(index.find_prefix("models/x")).is_empty());
+    assert!(names(index.find_prefix("zzz")).is_empty());
+    assert!(names(index.find_prefix("models/jeep_lod1.rbm.bak")).is_empty());
+    assert!(names(index.find_glob("zzz*")).is_empty());
+}
+
+#[test]
+fn queries_ignore_case_and_separators() {
+    let index = index();
+    assert_eq!(
+        names(index.find_prefix("MODELS\\JEEP")),
+        ["Models\\Jeep_LOD1.rbm", "models/jeep_lod2.rbm"]
+    );
+    assert_eq!(
+        names(index.find_substring("JEEP_DIF")),
+        ["textures/jeep_dif.dds"]
+    );
+    assert_eq!(
+        names(index.find_glob("models/JEEP_LOD1.*")),
+        ["Models\\Jeep_LOD1.rbm"]
+    );
+
+    // The same name under the same hash is only indexed once, whatever its case
+    let mut index = index.clone();
+    let hash = HashString::from_str(NAMES[0]);
+    index.insert(hash, "models/jeep_lod1.rbm");
+    assert_eq!(index.len(), NAMES.len());
+}
+
+#[test]
+fn file_lists_are_indexed() {
+    let index = HashIndex::read(Cursor::new(NAMES.join("\n"))).expect("list should read");
+    assert_eq!(index.len(), NAMES.len());
+    assert_eq!(
+        index.find_prefix("models/jeep_lod2").next(),
+        Some((
+            HashString::from_str("jeep_lod2.rbm"),
+            "models/jeep_lod2.rbm"
+        ))
+    );
+}
@@ -0,0 +1,206 @@
+use std::{io::Cursor, path::Path};
+
+use jc2_hashing::{HashCollisions, HashConfidence, HashEntry, HashList, HashListError, HashString};
+
+fn write_list(list: &HashList) -> String {
+    let mut writer = Vec::new();
+    list.write(&mut writer).expect("list should write");
+    String::from_utf8(writer).expect("list should be utf-8")
+}
+
+fn list_with(name: &str, source: Option<&str>, confidence: HashConfidence) -> HashList {
+    let mut list = HashList::new();
+    list.insert_string(name);
+    let metadata = list
+        .metadata_mut(HashString::from_str(name))
+        .expect("name was just inserted");
+    metadata.source = source.map(Into::into);
+    metadata.confidence = confidence;
+    list
+}
+
+#[test]
+fn tab_separated_lists_round_trip() {
+    let vehicle = HashString::from_str("arve.ee");
+    let text = format!(
+        "# comment\n\
+         {vehicle:08x}\tarve.ee\tpath\tsource=vehicles.filelist\tconfidence=high\n\
+         {vehicle:08x}\tARVE.EE\talternate\n\
+         {:08x}\tjeep\n",
+        HashString::from_str("jeep").hash(),
+        vehicle = vehicle.hash(),
+    );
+
+    let list = HashList::read(Cursor::new(&text)).expect("list should read");
+    assert_eq!(list.len(), 2);
+    assert_eq!(list.find_path(vehicle), Some(Path::new("arve.ee")));
+    let metadata = list.metadata(vehicle).expect("metadata should be kept");
+    assert_eq!(metadata.source.as_deref(), Some("vehicles.filelist"));
+    assert_eq!(metadata.confidence, HashConfidence::High);
+    assert_eq!(metadata.alternates, [HashEntry::String("ARVE.EE".into())]);
+
+    // Comments are dropped and lines come out sorted by hash
+    let mut lines: Vec<_> = text.lines().skip(1).collect();
+    lines.sort_by_key(|line| &line[..8]);
+    let mut expected = lines.join("\n");
+    expected.push('\n');
+    assert_eq!(write_list(&list), expected);
+}
+
+#[test]
+fn plain_file_lists_hash_file_names() {
+    let list = HashList::read(Cursor::new(
+        "exported\\vehicles\\arve.ee\r\nmodels/jeep.rbm\n",
+    ))
+    .expect("list should read");
+    assert_eq!(
+        list.find_path(HashString::from_str("arve.ee")),
+        Some(Path::new("exported\\vehicles\\arve.ee"))
+    );
+    assert_eq!(
+        list.find_path(HashString::from_str("jeep.rbm")),
+        Some(Path::new("models/jeep.rbm"))
+    );
+}
+
+#[test]
+fn invalid_lines_are_rejected() {
+    assert!(matches!(
+        HashList::read(Cursor::new("zzzzzzzz\tname\n")),
+        Err(HashListError::InvalidHash { line: 1, .. })
+    ));
+    assert!(matches!(
+        HashList::read(Cursor::new("\n00000001\tname\tconfidence=certain\n")),
+        Err(HashListError::InvalidField { line: 2, .. })
+    ));
+}
+
+#[test]
+fn confidence_parses_its_own_names() {
+    for confidence in [
+        HashConfidence::Low,
+        HashConfidence::Medium,
+        HashConfidence::High,
+    ] {
+        assert_eq!(confidence.as_str().parse(), Ok(confidence));
+    }
+    assert_eq!("High".parse::<HashConfidence>(), Err(()));
+    assert_eq!("".parse::<HashConfidence>(), Err(()));
+}
+
+#[test]
+fn merge_prefers_higher_confidence() {
+    let hash = HashString::from_str("arve.ee");
+
+    // Their name wins, ours is kept as an alternate
+    let mut list = list_with("arve.ee", Some("ours"), HashConfidence::Low);
+    let mut theirs = HashList::new();
+    theirs.extend([(hash, HashEntry::String("ARVE.EE".into()))]);
+    let metadata = theirs.metadata_mut(hash).expect("name was just inserted");
+    metadata.source = Some("theirs".into());
+    metadata.confidence = HashConfidence::High;
+    list.merge(theirs);
+    assert_eq!(list.find_string(hash).map(String::as_str), Some("ARVE.EE"));
+    let metadata = list.metadata(hash).expect("metadata should be kept");
+    assert_eq!(metadata.source.as_deref(), Some("theirs"));
+    assert_eq!(metadata.confidence, HashConfidence::High);
+    assert_eq!(metadata.alternates, [HashEntry::String("arve.ee".into())]);
+
+    // Ties favor our name, which doesn't take the source of theirs
+    let mut list = list_with("arve.ee", None, HashConfidence::Medium);
+    let mut theirs = HashList::new();
+    theirs.extend([(hash, HashEntry::String("ARVE.EE".into()))]);
+    theirs
+        .metadata_mut(hash)
+        .expect("name was just inserted")
+        .source = Some("theirs".into());
+    list.merge(theirs);
+    assert_eq!(list.find_string(hash).map(String::as_str), Some("arve.ee"));
+    let metadata = list.metadata(hash).expect("metadata should be kept");
+    assert_eq!(metadata.source, None);
+    assert_eq!(metadata.alternates, [HashEntry::String("ARVE.EE".into())]);
+
+    // The same name takes their source when we have none
+    let mut list = list_with("arve.ee", None, HashConfidence::Low);
+    list.merge(list_with("arve.ee", Some("theirs"), HashConfidence::High));
+    let metadata = list.metadata(hash).expect("metadata should be kept");
+    assert_eq!(metadata.source.as_deref(), Some("theirs"));
+    assert_eq!(metadata.confidence, HashConfidence::High);
+    assert!(metadata.alternates.is_empty());
+}
+
+#[test]
+fn merge_drops_our_source_when_their_name_wins() {
+    let hash = HashString::from_str("arve.ee");
+    let mut list = list_with("arve.ee", Some("ours"), HashConfidence::Low);
+    let mut theirs = HashList::new();
+    theirs.extend([(hash, HashEntry::String("ARVE.EE".into()))]);
+    theirs
+        .metadata_mut(hash)
+        .expect("name was just inserted")
+        .confidence = HashConfidence::High;
+    list.merge(theirs);
+
+    assert_eq!(list.find_string(hash).map(String::as_str), Some("ARVE.EE"));
+    let metadata = list.metadata(hash).expect("metadata should be kept");
+    assert_eq!(metadata.source, None);
+    assert_eq!(metadata.confidence, HashConfidence::High);
+}
+
+#[test]
+fn merge_adds_new_names() {
+    let mut list = HashList::new();
+    list.insert_string("jeep");
+    list.merge(list_with("arve.ee", Some("theirs"), HashConfidence::High));
+
+    assert_eq!(list.len(), 2);
+    let metadata = list
+        .metadata(HashString::from_str("arve.ee"))
+        .expect("metadata should be merged");
+    assert_eq!(metadata.source.as_deref(), Some("theirs"));
+    assert!(list.metadata(HashString::from_str("jeep")).is_none());
+}
+
+#[test]
+fn kept_collisions_are_found() {
+    let hash = HashString::from_str("arve.ee");
+    let mut list = HashList::new().with_collisions(HashCollisions::Keep);
+    assert_eq!(list.insert_string("arve.ee"), None);
+    assert_eq!(list.insert_path("ARVE.EE"), None);
+    list.extend([
+        (hash, HashEntry::String("Arve.ee".into())),
+        (hash, HashEntry::String("arve.ee".into())),
+    ]);
+    list.insert_string("jeep");
+
+    let names = [
+        HashEntry::String("arve.ee".into()),
+        HashEntry::Path("ARVE.EE".into()),
+        HashEntry::String("Arve.ee".into()),
+    ];
+    assert_eq!(list.len(), 2);
+    assert_eq!(list.find(hash), Some(&names[0]));
+    assert!(list.find_all(hash).eq(&names));
+    assert!(list.find_all_paths(hash).eq([Path::new("ARVE.EE")]));
+
+    let mut all: Vec<_> = list.iter_all().collect();
+    all.sort_by_key(|(hash, _)| **hash);
+    let jeep = (
+        HashString::from_str("jeep"),
+        HashEntry::String("jeep".into()),
+    );
+    let mut expected: Vec<_> = names.iter().map(|name| (&hash, name)).collect();
+    expected.push((&jeep.0, &jeep.1));
+    expected.sort_by_key(|(hash, _)| **hash);
+    assert_eq!(all, expected);
+
+    // Replacing lists only ever keep the last name
+    let mut list = HashList::new();
+    list.extend([
+        (hash, HashEntry::String("arve.ee".into())),
+        (hash, HashEntry::String("ARVE.EE".into())),
+    ]);
+    assert!(list
+        .find_all(hash)
+        .eq([&HashEntry::String("ARVE.EE".into())]));
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "serde")]
+
+use jc2_hashing::{HashEntry, HashList, HashString};
+
+#[test]
+fn backslash_paths_hash_their_file_name() {
+    let hash: HashString =
+        serde_json::from_str(r#""exported\\vehicles\\arve.ee""#).expect("name should read");
+    assert_eq!(hash.hash(), 2010063580);
+    assert_eq!(hash, HashString::from_str("arve.ee"));
+}
+
+#[test]
+fn names_round_trip_through_the_dictionary() {
+    let mut dictionary = HashList::new();
+    dictionary.extend([(
+        HashString::from_str("arve.ee"),
+        HashEntry::Path(r"exported\vehicles\arve.ee".into()),
+    )]);
+    dictionary.insert_string("rico");
+    let hashes = [
+        HashString::from_str("arve.ee"),
+        HashString::from_str("rico"),
+        HashString::new(0x1234_5678),
+    ];
+
+    let json = HashString::with_dictionary(dictionary, || {
+        serde_json::to_string(&hashes).expect("hashes should write")
+    });
+    assert_eq!(
+        json,
+        r#"["exported\\vehicles\\arve.ee","rico","0x12345678"]"#
+    );
+
+    // Without a dictionary, names are hashed again
+    let read: Vec<HashString> = serde_json::from_str(&json).expect("hashes should read");
+    assert_eq!(read, hashes);
+}
@@ -0,0 +1,94 @@
+use jc2_hashing::{
+    hash_little32, hash_little32_batch, hash_little32_with_seed, hash_little64, HashAlgorithm,
+    HashString, HashString64, Little32Hasher, Little32Seeded, Little64, LITTLE32_BATCH_LANES,
+};
+
+/// Inputs straddling the 12 byte block size, which is where the final block handling differs.
+fn inputs() -> Vec<Vec<u8>> {
+    [0, 1, 4, 11, 12, 13, 23, 24, 25, 64]
+        .into_iter()
+        .map(|length| (0..length).map(|i| b'A' + (i % 26) as u8).collect())
+        .collect()
+}
+
+#[test]
+fn hasher_matches_hash() {
+    for input in inputs() {
+        for chunk_size in [1, 5, 12, 13] {
+            let mut hasher = Little32Hasher::new(input.len());
+            for chunk in input.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(
+                hasher.finish(),
+                Some(hash_little32(&input)),
+                "length {} in chunks of {chunk_size}",
+                input.len()
+            );
+        }
+    }
+}
+
+#[test]
+fn case_folded_hasher_matches_lowercase_hash() {
+    for input in inputs() {
+        let mut hasher = Little32Hasher::new_case_folded(input.len());
+        hasher.update(&input);
+        assert_eq!(
+            hasher.finish(),
+            Some(hash_little32(&input.to_ascii_lowercase())),
+            "length {}",
+            input.len()
+        );
+    }
+}
+
+#[test]
+fn hasher_rejects_wrong_length() {
+    let mut hasher = Little32Hasher::new(13);
+    hasher.update(&[0u8; 12]);
+    assert_eq!(hasher.finish(), None);
+    hasher.update(&[0u8; 2]);
+    assert_eq!(hasher.finish(), None);
+}
+
+#[test]
+fn batch_matches_hash() {
+    // Equal lengths fill whole lanes, with a remainder that falls back to the scalar hash
+    for length in [0, 12, 13] {
+        let inputs: Vec<Vec<u8>> = (0..LITTLE32_BATCH_LANES * 2 + 3)
+            .map(|i| (0..length).map(|j| (i * 31 + j) as u8).collect())
+            .collect();
+        let expected: Vec<u32> = inputs.iter().map(|input| hash_little32(input)).collect();
+        assert_eq!(hash_little32_batch(&inputs), expected, "length {length}");
+    }
+
+    // Mixed lengths have to come back in input order
+    let mut mixed = inputs();
+    mixed.extend(inputs().into_iter().rev());
+    mixed.extend(inputs());
+    let expected: Vec<u32> = mixed.iter().map(|input| hash_little32(input)).collect();
+    assert_eq!(hash_little32_batch(&mixed), expected);
+    assert!(hash_little32_batch::<&[u8]>(&[]).is_empty());
+}
+
+/// Outputs of the reference `hashlittle` and `hashlittle2` from Bob Jenkins' `lookup3.c`.
+#[test]
+fn hashes_match_reference() {
+    let input = b"Four score and seven years ago";
+    assert_eq!(hash_little32(input), 0x17770551);
+    assert_eq!(hash_little32_with_seed(input, 1), 0xcd628161);
+    assert_eq!(hash_little64(input), 0xce7226e6_17770551);
+    assert_eq!(
+        Little32Seeded::<1>::hash(input),
+        HashString::new(0xcd628161)
+    );
+    assert_eq!(
+        Little64::hash(input),
+        HashString64::new(0xce7226e6_17770551)
+    );
+
+    // Empty input is still mixed, where the reference returns its initial state
+    assert_eq!(hash_little64(b"") as u32, hash_little32(b""));
+    assert_ne!(hash_little32(b""), 0xdeadbeef);
+}
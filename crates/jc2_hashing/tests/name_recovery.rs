@@ -0,0 +1,83 @@
+use std::num::NonZeroUsize;
+
+use jc2_hashing::{HashList, HashString, NameRecovery, NameRecoveryError, NameTemplate};
+
+fn recover(recovery: &NameRecovery) -> Vec<String> {
+    let list = recovery.run(|_| {}).expect("recovery should run");
+    let mut names: Vec<String> = list
+        .values()
+        .filter_map(|entry| entry.as_string().cloned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn templates_expand_every_combination() {
+    let template = NameTemplate::parse("{prefix}_{n}.RBM").expect("template should parse");
+    assert_eq!(template.as_str(), "{prefix}_{n}.RBM");
+    assert_eq!(template.placeholders().collect::<Vec<_>>(), ["prefix", "n"]);
+
+    let expected = [
+        "arve_00.rbm",
+        "arve_01.rbm",
+        "arve_02.rbm",
+        "jeep_00.rbm",
+        "jeep_01.rbm",
+        "jeep_02.rbm",
+    ];
+    let recovery = NameRecovery::new(expected.map(HashString::from_str))
+        .with_threads(NonZeroUsize::new(4).expect("thread count is not zero"))
+        .with_vocabulary("prefix", ["ARVE", "jeep", "arve"])
+        .with_range("n", 0..3, 2)
+        .with_template(template);
+    assert_eq!(recovery.candidate_count(), 6);
+    assert_eq!(recover(&recovery), expected);
+}
+
+#[test]
+fn invalid_templates_are_rejected() {
+    assert!(matches!(
+        NameTemplate::parse("{prefix"),
+        Err(NameRecoveryError::UnterminatedPlaceholder { .. })
+    ));
+
+    let recovery = NameRecovery::new([])
+        .with_template("{missing}.rbm".parse().expect("template should parse"));
+    assert!(matches!(
+        recovery.run(|_| {}),
+        Err(NameRecoveryError::UnknownPlaceholder { placeholder, .. }) if placeholder == "missing"
+    ));
+}
+
+#[test]
+fn known_names_recover_siblings() {
+    let mut known = HashList::new();
+    known.insert_string(r"exported\vehicles\arve.ee");
+    known.insert_string("models/jeep.rbm");
+    known.insert_string("readme");
+
+    let targets = [
+        r"exported\vehicles\jeep.ee",
+        "models/arve.rbm",
+        "models/readme",
+    ];
+    let recovery = NameRecovery::new(targets.map(HashString::from_str))
+        .with_names(["unrelated.txt"])
+        .with_known_names(&known)
+        .with_template(
+            r"{dir}\{stem}.{ext}"
+                .parse()
+                .expect("template should parse"),
+        )
+        .with_template("{dir}/{stem}.{ext}".parse().expect("template should parse"))
+        .with_template("{dir}/{stem}".parse().expect("template should parse"));
+
+    // Two directories, three stems and two extensions
+    assert_eq!(recovery.candidate_count(), 1 + 12 + 12 + 6);
+    assert_eq!(recover(&recovery), {
+        let mut targets = targets.map(String::from);
+        targets.sort();
+        targets
+    });
+}
@@ -0,0 +1,187 @@
+use proc_macro::{Delimiter, Group, Literal, Span, TokenStream, TokenTree};
+
+/// Expands to the little32 hash of a string literal as a `u32` literal, so it can be used
+/// anywhere a literal can, including match arms.
+///
+/// `hash!("Name" = 1234u32)` additionally fails to compile unless the hash equals the given value.
+#[proc_macro]
+pub fn hash(input: TokenStream) -> TokenStream {
+    expand_hash(input, Span::call_site()).unwrap_or_else(|error| error)
+}
+
+/// Replaces every `hash!(...)` inside the item, including its attributes, with the resulting
+/// literal. This lets attributes that only accept literals, like binrw's `magic`, use [`hash!`].
+///
+/// Must be placed above any attribute that should see the expanded literals.
+#[proc_macro_attribute]
+pub fn expand_hashes(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_nested(item)
+}
+
+fn expand_nested(stream: TokenStream) -> TokenStream {
+    let mut result = Vec::new();
+    let mut tokens = stream.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) if ident.to_string() == "hash" => {
+                let is_macro = matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == '!');
+                if !is_macro {
+                    result.push(TokenTree::Ident(ident));
+                    continue;
+                }
+                let bang = tokens.next();
+                match tokens.next() {
+                    Some(TokenTree::Group(group)) => {
+                        let expanded =
+                            expand_hash(group.stream(), ident.span()).unwrap_or_else(|error| error);
+                        result.extend(expanded);
+                    }
+                    other => {
+                        result.push(TokenTree::Ident(ident));
+                        result.extend(bang);
+                        result.extend(other);
+                    }
+                }
+            }
+            TokenTree::Group(group) => {
+                let mut expanded = Group::new(group.delimiter(), expand_nested(group.stream()));
+                expanded.set_span(group.span());
+                result.push(TokenTree::Group(expanded));
+            }
+            token => result.push(token),
+        }
+    }
+    result.into_iter().collect()
+}
+
+fn expand_hash(input: TokenStream, span: Span) -> Result<TokenStream, TokenStream> {
+    let mut tokens = input.into_iter();
+
+    let value = match tokens.next() {
+        Some(TokenTree::Literal(literal)) => parse_string(&literal).ok_or_else(|| {
+            compile_error("expected a string literal without escapes", literal.span())
+        })?,
+        // Macro arguments forwarded through macro_rules arrive wrapped in an invisible group
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::None => {
+            return expand_hash(group.stream(), span);
+        }
+        Some(token) => return Err(compile_error("expected a string literal", token.span())),
+        None => return Err(compile_error("expected a string literal", span)),
+    };
+    let hash = little32(value.as_bytes());
+
+    match (tokens.next(), tokens.next(), tokens.next()) {
+        (None, _, _) => {}
+        (Some(TokenTree::Punct(punct)), Some(TokenTree::Literal(literal)), None)
+            if punct.as_char() == '=' =>
+        {
+            let Some(expected) = parse_u32(&literal) else {
+                return Err(compile_error("expected a u32 literal", literal.span()));
+            };
+            if expected != hash {
+                let message = format!(
+                    "hash of {value:?} is {hash} (0x{hash:08X}), not {expected} (0x{expected:08X})"
+                );
+                return Err(compile_error(&message, literal.span()));
+            }
+        }
+        (Some(token), _, _) => {
+            return Err(compile_error(
+                "expected `\"name\"` or `\"name\" = value`",
+                token.span(),
+            ))
+        }
+    }
+
+    let mut literal = Literal::u32_suffixed(hash);
+    literal.set_span(span);
+    Ok(TokenTree::Literal(literal).into())
+}
+
+fn parse_string(literal: &Literal) -> Option<String> {
+    let source = literal.to_string();
+    let value = source.strip_prefix('"')?.strip_suffix('"')?;
+    (!value.contains('\\')).then(|| value.to_owned())
+}
+
+fn parse_u32(literal: &Literal) -> Option<u32> {
+    let source = literal.to_string().replace('_', "");
+    let source = source.strip_suffix("u32").unwrap_or(&source);
+    match source
+        .strip_prefix("0x")
+        .or_else(|| source.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => source.parse().ok(),
+    }
+}
+
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let tokens: TokenStream = format!("::core::compile_error!({message:?})")
+        .parse()
+        .unwrap_or_default();
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
+
+/// Mirrors `jc2_hashing::hash_little32`, which depends on this crate and so cannot be used here.
+fn little32(data: &[u8]) -> u32 {
+    let seed = 0xDEADBEEFu32.wrapping_add(data.len() as u32);
+    let (mut a, mut b, mut c) = (seed, seed, seed);
+
+    let mut blocks = data.chunks(12).peekable();
+    while let Some(block) = blocks.next() {
+        let mut padded = [0u8; 12];
+        padded[..block.len()].copy_from_slice(block);
+        let word =
+            |i: usize| u32::from_le_bytes([padded[i], padded[i + 1], padded[i + 2], padded[i + 3]]);
+        a = a.wrapping_add(word(0));
+        b = b.wrapping_add(word(4));
+        c = c.wrapping_add(word(8));
+
+        if blocks.peek().is_none() {
+            break;
+        }
+
+        a = a.wrapping_sub(c);
+        a ^= c.rotate_left(4);
+        c = c.wrapping_add(b);
+        b = b.wrapping_sub(a);
+        b ^= a.rotate_left(6);
+        a = a.wrapping_add(c);
+        c = c.wrapping_sub(b);
+        c ^= b.rotate_left(8);
+        b = b.wrapping_add(a);
+        a = a.wrapping_sub(c);
+        a ^= c.rotate_left(16);
+        c = c.wrapping_add(b);
+        b = b.wrapping_sub(a);
+        b ^= a.rotate_left(19);
+        a = a.wrapping_add(c);
+        c = c.wrapping_sub(b);
+        c ^= b.rotate_left(4);
+        b = b.wrapping_add(a);
+    }
+
+    c ^= b;
+    c = c.wrapping_sub(b.rotate_left(14));
+    a ^= c;
+    a = a.wrapping_sub(c.rotate_left(11));
+    b ^= a;
+    b = b.wrapping_sub(a.rotate_left(25));
+    c ^= b;
+    c = c.wrapping_sub(b.rotate_left(16));
+    a ^= c;
+    a = a.wrapping_sub(c.rotate_left(4));
+    b ^= a;
+    b = b.wrapping_sub(a.rotate_left(14));
+    c ^= b;
+    c = c.wrapping_sub(b.rotate_left(24));
+
+    c
+}
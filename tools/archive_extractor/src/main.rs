@@ -0,0 +1,208 @@
+use std::{
+    fs::File,
+    io::{BufReader, Cursor, Read, Seek, SeekFrom},
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use clap::Parser;
+use jc2_file_formats::{
+    archive::{ArchiveKind, ArchiveTable, ArchiveTableEntry, StreamArchive},
+    format::{detect_format, FileFormat},
+};
+use jc2_hashing::{HashList, HashString};
+
+#[derive(Parser)]
+struct Args {
+    /// `.tab` files to extract, each next to its `.arc` and optionally its `.filelist`
+    #[arg(required = true)]
+    tables: Vec<PathBuf>,
+    /// Directory to extract into, with a folder per archive
+    #[arg(short, long, default_value = ".")]
+    output: PathBuf,
+    /// Extra hash lists used to name entries missing from the `.filelist`
+    #[arg(long)]
+    hashes: Vec<PathBuf>,
+    /// Also extract the contents of stream archives into a folder next to them
+    #[arg(short, long)]
+    recursive: bool,
+    /// Number of threads, defaulting to one per core
+    #[arg(short, long)]
+    jobs: Option<usize>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let mut hashes = HashList::new();
+    for path in &args.hashes {
+        hashes.merge(HashList::read(BufReader::new(File::open(path)?))?);
+    }
+
+    let jobs = args
+        .jobs
+        .or_else(|| std::thread::available_parallelism().ok().map(Into::into))
+        .unwrap_or(1);
+
+    let mut failed = 0usize;
+    for table_path in &args.tables {
+        let table = ArchiveTable::read(&mut BufReader::new(File::open(table_path)?))?;
+        let mut names = hashes.clone();
+        let file_list = table_path.with_extension("filelist");
+        if file_list.is_file() {
+            names.merge(HashList::read(BufReader::new(File::open(file_list)?))?);
+        }
+
+        let stem = table_path.file_stem().unwrap_or_default();
+        let extractor = Extractor {
+            archive: table_path.with_extension("arc"),
+            root: args.output.join(stem),
+            names: &names,
+            recursive: args.recursive,
+        };
+
+        let mut entries: Vec<_> = table.entries.into_iter().collect();
+        entries.sort_unstable_by_key(|(_, entry)| entry.offset);
+        println!(
+            "extracting {} entries from {}",
+            entries.len(),
+            table_path.display()
+        );
+        failed += extractor.run(&entries, jobs)?;
+    }
+
+    if failed > 0 {
+        anyhow::bail!("failed to extract {failed} entries");
+    }
+    Ok(())
+}
+
+struct Extractor<'a> {
+    archive: PathBuf,
+    root: PathBuf,
+    names: &'a HashList,
+    recursive: bool,
+}
+
+impl Extractor<'_> {
+    /// Extracts `entries` on `jobs` threads, each with its own handle to the `.arc`, returning
+    /// how many failed.
+    fn run(
+        &self,
+        entries: &[(HashString, ArchiveTableEntry)],
+        jobs: usize,
+    ) -> anyhow::Result<usize> {
+        let next = AtomicUsize::new(0);
+        let failed = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..jobs.clamp(1, entries.len().max(1)))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut archive = File::open(&self.archive)?;
+                        while let Some((hash, entry)) =
+                            entries.get(next.fetch_add(1, Ordering::Relaxed))
+                        {
+                            if let Err(error) = self.extract(&mut archive, *hash, entry) {
+                                eprintln!("{:08x}: {error}", hash.hash());
+                                failed.fetch_add(1, Ordering::Relaxed);
+                            }
+                        }
+                        anyhow::Ok(())
+                    })
+                })
+                .collect();
+            for worker in workers {
+                match worker.join() {
+                    Ok(result) => result?,
+                    Err(panic) => std::panic::resume_unwind(panic),
+                }
+            }
+            Ok(failed.load(Ordering::Relaxed))
+        })
+    }
+
+    fn extract(
+        &self,
+        archive: &mut File,
+        hash: HashString,
+        entry: &ArchiveTableEntry,
+    ) -> anyhow::Result<()> {
+        let mut data = vec![0u8; entry.size as usize];
+        archive.seek(SeekFrom::Start(entry.offset as u64))?;
+        archive.read_exact(&mut data)?;
+
+        // Files sharing a name in different directories share an entry, so each gets a copy
+        let mut paths: Vec<PathBuf> = self
+            .names
+            .find_all(hash)
+            .filter_map(|name| match (name.as_path(), name.as_string()) {
+                (Some(path), _) => path.to_str().map(relative_path),
+                (None, Some(string)) => Some(relative_path(string)),
+                (None, None) => None,
+            })
+            .filter(|path| path.file_name().is_some())
+            .collect();
+        let named = !paths.is_empty();
+        if !named {
+            let extension = detect_format(&data).extension().unwrap_or("bin");
+            paths.push(Path::new("_unknown").join(format!("{:08x}.{extension}", hash.hash())));
+        }
+
+        for path in paths {
+            let path = self.root.join(path);
+            write(&path, &data)?;
+            if self.recursive {
+                extract_stream_archive(&path, &data, named)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Extracts a stream archive into a folder named after it, along with any archives inside it.
+/// Archives without a name are recognized by their contents instead.
+fn extract_stream_archive(path: &Path, data: &[u8], named: bool) -> anyhow::Result<()> {
+    let is_archive = if named {
+        ArchiveKind::from_path(path) == Some(ArchiveKind::Stream)
+    } else {
+        matches!(
+            detect_format(data),
+            FileFormat::StreamArchive | FileFormat::Zlib
+        )
+    };
+    if !is_archive {
+        return Ok(());
+    }
+
+    let archive = match StreamArchive::read(&mut Cursor::new(data)) {
+        Ok(archive) => archive,
+        // Unnamed zlib streams are not always archives
+        Err(_) if !named => return Ok(()),
+        Err(error) => return Err(error.into()),
+    };
+    let root = path.with_extension("");
+    for entry in &archive.entries {
+        let path = root.join(relative_path(&entry.name));
+        write(&path, &entry.data)?;
+        extract_stream_archive(&path, &entry.data, true)?;
+    }
+    Ok(())
+}
+
+fn write(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, data)
+}
+
+/// Converts a name from a file list into a path that stays inside the output directory.
+fn relative_path(name: &str) -> PathBuf {
+    Path::new(&name.replace('\\', "/"))
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(component) => Some(component),
+            _ => None,
+        })
+        .collect()
+}
@@ -1,13 +1,6 @@
-use jc2_file_formats::render_block_model::{Material, RenderBlock};
+use std::mem::{offset_of, size_of};
 
-mod billboard_foliage;
-mod deformable;
-mod facade;
-mod general;
-mod halo;
-mod simple;
-mod skinned;
-mod vegetation;
+use jc2_file_formats::render_block_model::{GenericMesh, MeshAttributes, PrimitiveType};
 
 type AccessorType = gltf_json::accessor::Type;
 type AccessorComponentType = gltf_json::accessor::ComponentType;
@@ -15,11 +8,6 @@ type MeshSemantic = gltf_json::mesh::Semantic;
 
 pub type GltfMeshAccessor = (AccessorType, AccessorComponentType, MeshSemantic, usize);
 
-pub trait GltfMeshAccessors {
-    fn accessors() -> Vec<GltfMeshAccessor>;
-    fn target_accessors() -> Option<Vec<GltfMeshAccessor>>;
-}
-
 pub type GltfMeshMode = gltf_json::mesh::Mode;
 
 pub trait GltfHelpers {
@@ -39,12 +27,56 @@ pub trait GltfHelpers {
     fn target_accessors(&self) -> Option<Vec<GltfMeshAccessor>>;
 }
 
-const fn count<T>(value: &[T]) -> usize {
-    value.len()
+/// Every attribute a [`GenericMesh`] can have, interleaved the way the buffer stores them.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct GltfVertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    tangent: [f32; 4],
+    uv0: [f32; 2],
+    uv1: [f32; 2],
+    uv2: [f32; 2],
+    color: [f32; 4],
+    bone_weights: [f32; 8],
+    bone_indices: [u32; 8],
+    morph_position: [f32; 3],
+    morph_normal: [f32; 3],
+    morph_tangent: [f32; 3],
 }
 
-const fn stride<T>(_: &[T]) -> usize {
-    std::mem::size_of::<T>()
+/// A [`GenericMesh`] along with its vertices as they are written to the buffer.
+pub struct GltfMesh<'a> {
+    mesh: GenericMesh<'a>,
+    vertices: Vec<GltfVertex>,
+}
+
+impl<'a> From<GenericMesh<'a>> for GltfMesh<'a> {
+    fn from(mesh: GenericMesh<'a>) -> Self {
+        let tangents = mesh.tangents();
+        let vertices = mesh
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(index, vertex)| GltfVertex {
+                position: vertex.position.into(),
+                normal: vertex.normal.into(),
+                tangent: tangents
+                    .as_ref()
+                    .map_or([1.0, 0.0, 0.0, 1.0], |tangents| tangents[index].into()),
+                uv0: vertex.uv0.into(),
+                uv1: vertex.uv1.into(),
+                uv2: vertex.uv2.into(),
+                color: vertex.diffuse_color.into(),
+                bone_weights: vertex.bone_weights,
+                bone_indices: vertex.bone_indices,
+                morph_position: vertex.morph_position.into(),
+                morph_normal: vertex.morph_normal.into(),
+                morph_tangent: vertex.morph_tangent.into(),
+            })
+            .collect();
+        Self { mesh, vertices }
+    }
 }
 
 #[inline]
@@ -57,23 +89,9 @@ fn bytes<T>(value: &[T]) -> &[u8] {
 }
 
 #[inline]
-fn textures(material: &Material) -> [&str; 8] {
-    [
-        material.textures[0].as_ref(),
-        material.textures[1].as_ref(),
-        material.textures[2].as_ref(),
-        material.textures[3].as_ref(),
-        material.textures[4].as_ref(),
-        material.textures[5].as_ref(),
-        material.textures[6].as_ref(),
-        material.textures[7].as_ref(),
-    ]
-}
-
-#[inline]
-fn mesh_mode(material: &Material) -> GltfMeshMode {
+fn mesh_mode(primitive_type: PrimitiveType) -> GltfMeshMode {
     use jc2_file_formats::render_block_model::PrimitiveType::*;
-    match material.primitive_type {
+    match primitive_type {
         TriangleList | IndexedTriangleList => GltfMeshMode::Triangles,
         TriangleStrip | IndexedTriangleStrip => GltfMeshMode::TriangleStrip,
         TriangleFan | IndexedTriangleFan => GltfMeshMode::TriangleFan,
@@ -82,194 +100,143 @@ fn mesh_mode(material: &Material) -> GltfMeshMode {
     }
 }
 
-#[inline]
-fn accessors<T: GltfMeshAccessors>(_: &[T]) -> Vec<GltfMeshAccessor> {
-    T::accessors()
-}
-
-#[inline]
-fn target_accessors<T: GltfMeshAccessors>(_: &[T]) -> Option<Vec<GltfMeshAccessor>> {
-    T::target_accessors()
-}
-
-impl GltfHelpers for RenderBlock {
+impl GltfHelpers for GltfMesh<'_> {
     #[inline]
     fn vertex_count(&self) -> usize {
-        match self {
-            RenderBlock::BillboardFoliage(data) => count(&data.vertices),
-            RenderBlock::CarPaint(data) => count(&data.vertices),
-            RenderBlock::CarPaintSimple(data) => count(&data.vertices),
-            RenderBlock::DeformableWindow(data) => count(&data.vertices),
-            RenderBlock::Facade(data) => count(&data.vertices),
-            RenderBlock::General(data) => count(&data.vertices),
-            RenderBlock::Halo(data) => count(&data.vertices),
-            RenderBlock::Lambert(data) => count(&data.vertices),
-            RenderBlock::SkinnedGeneral(data) => count(&data.vertices),
-            RenderBlock::VegetationBark(data) => count(&data.vertices),
-            RenderBlock::VegetationFoliage(data) => count(&data.vertices),
-            RenderBlock::Window(data) => count(&data.vertices),
-        }
+        self.vertices.len()
     }
 
     #[inline]
     fn index_count(&self) -> usize {
-        match self {
-            RenderBlock::BillboardFoliage(data) => count(&data.indices),
-            RenderBlock::CarPaint(data) => count(&data.indices),
-            RenderBlock::CarPaintSimple(data) => count(&data.indices),
-            RenderBlock::DeformableWindow(data) => count(&data.indices),
-            RenderBlock::Facade(data) => count(&data.indices),
-            RenderBlock::General(data) => count(&data.indices),
-            RenderBlock::Halo(data) => count(&data.indices),
-            RenderBlock::Lambert(data) => count(&data.indices),
-            RenderBlock::SkinnedGeneral(data) => count(&data.indices),
-            RenderBlock::VegetationBark(data) => count(&data.indices),
-            RenderBlock::VegetationFoliage(data) => count(&data.indices),
-            RenderBlock::Window(data) => count(&data.indices),
-        }
+        self.mesh.indices.len()
     }
 
     #[inline]
     fn vertex_stride(&self) -> usize {
-        match self {
-            RenderBlock::BillboardFoliage(data) => stride(&data.vertices),
-            RenderBlock::CarPaint(data) => stride(&data.vertices),
-            RenderBlock::CarPaintSimple(data) => stride(&data.vertices),
-            RenderBlock::DeformableWindow(data) => stride(&data.vertices),
-            RenderBlock::Facade(data) => stride(&data.vertices),
-            RenderBlock::General(data) => stride(&data.vertices),
-            RenderBlock::Halo(data) => stride(&data.vertices),
-            RenderBlock::Lambert(data) => stride(&data.vertices),
-            RenderBlock::SkinnedGeneral(data) => stride(&data.vertices),
-            RenderBlock::VegetationBark(data) => stride(&data.vertices),
-            RenderBlock::VegetationFoliage(data) => stride(&data.vertices),
-            RenderBlock::Window(data) => stride(&data.vertices),
-        }
+        size_of::<GltfVertex>()
     }
 
     #[inline]
     fn index_stride(&self) -> usize {
-        match self {
-            RenderBlock::BillboardFoliage(data) => stride(&data.indices),
-            RenderBlock::CarPaint(data) => stride(&data.indices),
-            RenderBlock::CarPaintSimple(data) => stride(&data.indices),
-            RenderBlock::DeformableWindow(data) => stride(&data.indices),
-            RenderBlock::Facade(data) => stride(&data.indices),
-            RenderBlock::General(data) => stride(&data.indices),
-            RenderBlock::Halo(data) => stride(&data.indices),
-            RenderBlock::Lambert(data) => stride(&data.indices),
-            RenderBlock::SkinnedGeneral(data) => stride(&data.indices),
-            RenderBlock::VegetationBark(data) => stride(&data.indices),
-            RenderBlock::VegetationFoliage(data) => stride(&data.indices),
-            RenderBlock::Window(data) => stride(&data.indices),
-        }
+        size_of::<u16>()
     }
 
     #[inline]
     fn vertices_as_bytes(&self) -> &[u8] {
-        match self {
-            RenderBlock::BillboardFoliage(data) => bytes(&data.vertices),
-            RenderBlock::CarPaint(data) => bytes(&data.vertices),
-            RenderBlock::CarPaintSimple(data) => bytes(&data.vertices),
-            RenderBlock::DeformableWindow(data) => bytes(&data.vertices),
-            RenderBlock::Facade(data) => bytes(&data.vertices),
-            RenderBlock::General(data) => bytes(&data.vertices),
-            RenderBlock::Halo(data) => bytes(&data.vertices),
-            RenderBlock::Lambert(data) => bytes(&data.vertices),
-            RenderBlock::SkinnedGeneral(data) => bytes(&data.vertices),
-            RenderBlock::VegetationBark(data) => bytes(&data.vertices),
-            RenderBlock::VegetationFoliage(data) => bytes(&data.vertices),
-            RenderBlock::Window(data) => bytes(&data.vertices),
-        }
+        bytes(&self.vertices)
     }
 
     #[inline]
     fn indices_as_bytes(&self) -> &[u8] {
-        match self {
-            RenderBlock::BillboardFoliage(data) => bytes(&data.indices),
-            RenderBlock::CarPaint(data) => bytes(&data.indices),
-            RenderBlock::CarPaintSimple(data) => bytes(&data.indices),
-            RenderBlock::DeformableWindow(data) => bytes(&data.indices),
-            RenderBlock::Facade(data) => bytes(&data.indices),
-            RenderBlock::General(data) => bytes(&data.indices),
-            RenderBlock::Halo(data) => bytes(&data.indices),
-            RenderBlock::Lambert(data) => bytes(&data.indices),
-            RenderBlock::SkinnedGeneral(data) => bytes(&data.indices),
-            RenderBlock::VegetationBark(data) => bytes(&data.indices),
-            RenderBlock::VegetationFoliage(data) => bytes(&data.indices),
-            RenderBlock::Window(data) => bytes(&data.indices),
-        }
+        bytes(self.mesh.indices)
     }
 
     #[inline]
     fn textures(&self) -> [&str; 8] {
-        match self {
-            RenderBlock::BillboardFoliage(data) => textures(&data.material),
-            RenderBlock::CarPaint(data) => textures(&data.material),
-            RenderBlock::CarPaintSimple(data) => textures(&data.material),
-            RenderBlock::DeformableWindow(data) => textures(&data.material),
-            RenderBlock::Facade(data) => textures(&data.material),
-            RenderBlock::General(data) => textures(&data.material),
-            RenderBlock::Halo(data) => textures(&data.material),
-            RenderBlock::Lambert(data) => textures(&data.material),
-            RenderBlock::SkinnedGeneral(data) => textures(&data.material),
-            RenderBlock::VegetationBark(data) => textures(&data.material),
-            RenderBlock::VegetationFoliage(data) => textures(&data.material),
-            RenderBlock::Window(data) => textures(&data.material),
-        }
+        self.mesh.textures
     }
 
     #[inline]
     fn mesh_mode(&self) -> GltfMeshMode {
-        match self {
-            RenderBlock::BillboardFoliage(data) => mesh_mode(&data.material),
-            RenderBlock::CarPaint(data) => mesh_mode(&data.material),
-            RenderBlock::CarPaintSimple(data) => mesh_mode(&data.material),
-            RenderBlock::DeformableWindow(data) => mesh_mode(&data.material),
-            RenderBlock::Facade(data) => mesh_mode(&data.material),
-            RenderBlock::General(data) => mesh_mode(&data.material),
-            RenderBlock::Halo(data) => mesh_mode(&data.material),
-            RenderBlock::Lambert(data) => mesh_mode(&data.material),
-            RenderBlock::SkinnedGeneral(data) => mesh_mode(&data.material),
-            RenderBlock::VegetationBark(data) => mesh_mode(&data.material),
-            RenderBlock::VegetationFoliage(data) => mesh_mode(&data.material),
-            RenderBlock::Window(data) => mesh_mode(&data.material),
-        }
+        mesh_mode(self.mesh.primitive_type)
     }
 
-    #[inline]
     fn accessors(&self) -> Vec<GltfMeshAccessor> {
-        match self {
-            RenderBlock::BillboardFoliage(data) => accessors(&data.vertices),
-            RenderBlock::CarPaint(data) => accessors(&data.vertices),
-            RenderBlock::CarPaintSimple(data) => accessors(&data.vertices),
-            RenderBlock::DeformableWindow(data) => accessors(&data.vertices),
-            RenderBlock::Facade(data) => accessors(&data.vertices),
-            RenderBlock::General(data) => accessors(&data.vertices),
-            RenderBlock::Halo(data) => accessors(&data.vertices),
-            RenderBlock::Lambert(data) => accessors(&data.vertices),
-            RenderBlock::SkinnedGeneral(data) => accessors(&data.vertices),
-            RenderBlock::VegetationBark(data) => accessors(&data.vertices),
-            RenderBlock::VegetationFoliage(data) => accessors(&data.vertices),
-            RenderBlock::Window(data) => accessors(&data.vertices),
+        type A = MeshAttributes;
+        let attributes = self.mesh.attributes;
+        let mut result = Vec::new();
+        for (attribute, accessor_type, semantic, offset) in [
+            (
+                A::POSITION,
+                AccessorType::Vec3,
+                MeshSemantic::Positions,
+                offset_of!(GltfVertex, position),
+            ),
+            (
+                A::NORMAL,
+                AccessorType::Vec3,
+                MeshSemantic::Normals,
+                offset_of!(GltfVertex, normal),
+            ),
+            (
+                A::TANGENT,
+                AccessorType::Vec4,
+                MeshSemantic::Tangents,
+                offset_of!(GltfVertex, tangent),
+            ),
+            (
+                A::UV0,
+                AccessorType::Vec2,
+                MeshSemantic::TexCoords(0),
+                offset_of!(GltfVertex, uv0),
+            ),
+            (
+                A::UV1,
+                AccessorType::Vec2,
+                MeshSemantic::TexCoords(1),
+                offset_of!(GltfVertex, uv1),
+            ),
+            (
+                A::UV2,
+                AccessorType::Vec2,
+                MeshSemantic::TexCoords(2),
+                offset_of!(GltfVertex, uv2),
+            ),
+            (
+                A::COLOR,
+                AccessorType::Vec4,
+                MeshSemantic::Colors(0),
+                offset_of!(GltfVertex, color),
+            ),
+        ] {
+            if attributes.contains(attribute) {
+                result.push((accessor_type, AccessorComponentType::F32, semantic, offset));
+            }
         }
+        if attributes.contains(A::SKIN) {
+            for i in 0..self.mesh.bone_influences {
+                result.push((
+                    AccessorType::Scalar,
+                    AccessorComponentType::F32,
+                    MeshSemantic::Weights(i as u32),
+                    offset_of!(GltfVertex, bone_weights) + size_of::<f32>() * i,
+                ));
+                result.push((
+                    AccessorType::Scalar,
+                    AccessorComponentType::U32,
+                    MeshSemantic::Joints(i as u32),
+                    offset_of!(GltfVertex, bone_indices) + size_of::<u32>() * i,
+                ));
+            }
+        }
+        result
     }
 
-    #[inline]
     fn target_accessors(&self) -> Option<Vec<GltfMeshAccessor>> {
-        match self {
-            RenderBlock::BillboardFoliage(data) => target_accessors(&data.vertices),
-            RenderBlock::CarPaint(data) => target_accessors(&data.vertices),
-            RenderBlock::CarPaintSimple(data) => target_accessors(&data.vertices),
-            RenderBlock::DeformableWindow(data) => target_accessors(&data.vertices),
-            RenderBlock::Facade(data) => target_accessors(&data.vertices),
-            RenderBlock::General(data) => target_accessors(&data.vertices),
-            RenderBlock::Halo(data) => target_accessors(&data.vertices),
-            RenderBlock::Lambert(data) => target_accessors(&data.vertices),
-            RenderBlock::SkinnedGeneral(data) => target_accessors(&data.vertices),
-            RenderBlock::VegetationBark(data) => target_accessors(&data.vertices),
-            RenderBlock::VegetationFoliage(data) => target_accessors(&data.vertices),
-            RenderBlock::Window(data) => target_accessors(&data.vertices),
-        }
+        self.mesh
+            .attributes
+            .contains(MeshAttributes::MORPH)
+            .then(|| {
+                vec![
+                    (
+                        AccessorType::Vec3,
+                        AccessorComponentType::F32,
+                        MeshSemantic::Positions,
+                        offset_of!(GltfVertex, morph_position),
+                    ),
+                    (
+                        AccessorType::Vec3,
+                        AccessorComponentType::F32,
+                        MeshSemantic::Normals,
+                        offset_of!(GltfVertex, morph_normal),
+                    ),
+                    (
+                        AccessorType::Vec3,
+                        AccessorComponentType::F32,
+                        MeshSemantic::Tangents,
+                        offset_of!(GltfVertex, morph_tangent),
+                    ),
+                ]
+            })
     }
 }
@@ -4,9 +4,9 @@ use anyhow::Context;
 use clap::Parser;
 use gltf_json::{buffer::Stride, mesh::MorphTarget, validation::Checked, Index};
 use helpers::GltfMeshAccessor;
-use jc2_file_formats::render_block_model::RenderBlockModel;
+use jc2_file_formats::render_block_model::{RenderBlock, RenderBlockModel};
 
-use crate::helpers::GltfHelpers;
+use crate::helpers::{GltfHelpers, GltfMesh};
 
 mod helpers;
 
@@ -195,10 +195,18 @@ fn main() -> anyhow::Result<()> {
     let file = std::fs::File::open(args.file.clone())?;
     let rbm = RenderBlockModel::read(&mut std::io::BufReader::new(file))?;
 
+    // Blocks that couldn't be parsed have no geometry to export
+    let meshes: Vec<GltfMesh> = rbm
+        .blocks
+        .iter()
+        .filter_map(RenderBlock::as_mesh)
+        .map(GltfMesh::from)
+        .collect();
+
     // First pass, calculate necessary buffer size, and round up to nearest multiple of 4
     let mut buffer_size = 0;
 
-    for block in rbm.blocks.iter() {
+    for block in &meshes {
         buffer_size += block.vertices_as_bytes().len();
         buffer_size += block.indices_as_bytes().len();
     }
@@ -208,7 +216,7 @@ fn main() -> anyhow::Result<()> {
     // Second pass create the final buffer
     let mut buffer = Vec::with_capacity(buffer_size);
 
-    for block in rbm.blocks.iter() {
+    for block in &meshes {
         buffer.extend_from_slice(block.vertices_as_bytes());
         buffer.extend_from_slice(block.indices_as_bytes());
     }
@@ -237,9 +245,9 @@ fn main() -> anyhow::Result<()> {
 
     // Next pass, create the final gltf
     let mut buffer_offset = 0;
-    let mut nodes = Vec::with_capacity(rbm.blocks.len());
+    let mut nodes = Vec::with_capacity(meshes.len());
 
-    for block in rbm.blocks.iter() {
+    for block in &meshes {
         let mut primitive = MeshPrimitive {
             attributes: Default::default(),
             extensions: Default::default(),
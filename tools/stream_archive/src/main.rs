@@ -1,38 +1,107 @@
 use std::path::PathBuf;
 
-use clap::Parser;
-use jc2_file_formats::archive::StreamArchive;
+use clap::{Parser, ValueEnum};
+use jc2_file_formats::archive::{
+    StreamArchive, StreamArchiveCompression, StreamArchiveEndian, StreamArchiveLayout,
+};
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Endian {
+    /// PC
+    Little,
+    /// Consoles
+    Big,
+}
+
+impl From<Endian> for StreamArchiveEndian {
+    fn from(value: Endian) -> Self {
+        match value {
+            Endian::Little => Self::Little,
+            Endian::Big => Self::Big,
+        }
+    }
+}
 
 #[derive(Parser)]
 struct Args {
     #[arg()]
     file: PathBuf,
+    /// Archive to create when packing a directory, compressed if the extension ends in `z`
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Zlib compression level, from 0 to 9
+    #[arg(short, long, default_value_t = StreamArchiveCompression::DEFAULT_LEVEL)]
+    level: u32,
+    /// Endianness of the packed archive. Given an archive, converts it into `output` instead of
+    /// extracting it
+    #[arg(short, long)]
+    endian: Option<Endian>,
+    /// Store identical files once
+    #[arg(long)]
+    dedup: bool,
+    /// Start files with an extension at a multiple of some bytes, such as `dds=16`
+    #[arg(long, value_parser = parse_alignment)]
+    align: Vec<(String, usize)>,
+}
+
+fn parse_alignment(value: &str) -> Result<(String, usize), String> {
+    let (extension, alignment) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected EXTENSION=ALIGNMENT, got {value:?}"))?;
+    let alignment = alignment.parse().map_err(|error| format!("{error}"))?;
+    Ok((extension.into(), alignment))
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    let layout = args.align.iter().fold(
+        StreamArchiveLayout::new().with_dedup(args.dedup),
+        |layout, (extension, alignment)| layout.with_alignment(extension, *alignment),
+    );
 
     if args.file.is_dir() {
-        let mut archive = StreamArchive::default();
+        let mut files = Vec::new();
         for file in std::fs::read_dir(args.file.clone())? {
             let file = file?;
-            if !file.path().is_file() {
-                continue;
+            if file.path().is_file() {
+                files.push(file);
             }
-            let file_name = file.file_name();
-            archive.entries.insert(
-                file_name.to_string_lossy().into(),
+        }
+        files.sort_by_key(std::fs::DirEntry::file_name);
+
+        let mut archive = StreamArchive::default()
+            .with_endian(args.endian.map_or_else(Default::default, Into::into));
+        for file in files {
+            archive.insert(
+                file.file_name().to_string_lossy(),
                 std::fs::read(file.path())?,
             );
         }
-        archive.write(&mut std::fs::File::create(args.file.with_extension("ee"))?)?;
+        let output = args
+            .output
+            .unwrap_or_else(|| args.file.with_extension("ee"));
+        let compression = StreamArchiveCompression::from_path(&output).with_level(args.level);
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(output)?);
+        archive.write_compressed_with_layout(&mut writer, compression, &layout)?;
     } else if args.file.is_file() {
         let file = std::fs::File::open(args.file.clone())?;
         let archive = StreamArchive::read(&mut std::io::BufReader::new(file))?;
+        if let Some(endian) = args.endian {
+            let Some(output) = args.output else {
+                anyhow::bail!("converting an archive requires an output path");
+            };
+            let compression = StreamArchiveCompression::from_path(&output).with_level(args.level);
+            let mut writer = std::io::BufWriter::new(std::fs::File::create(output)?);
+            archive
+                .with_endian(endian.into())
+                .write_compressed_with_layout(&mut writer, compression, &layout)?;
+            return Ok(());
+        }
+
         let root = args.file.with_extension("");
         std::fs::create_dir(&root)?;
-        for (name, data) in &archive.entries {
-            std::fs::write(root.clone().join(name), data)?;
+        for entry in &archive.entries {
+            std::fs::write(root.clone().join(&entry.name), &entry.data)?;
         }
     }
 